@@ -0,0 +1,183 @@
+use sasm_interpreter::{io::IoBackend, limits::Limits, Interpreter};
+use std::{str::FromStr, time::Duration};
+
+/// What the interpreter should do with the script.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Execute the script (or start the REPL).
+    #[default]
+    Run,
+    /// Check the script for likely mistakes without executing it.
+    Lint,
+    /// Rewrite the script in its canonical format.
+    Fmt,
+}
+
+impl Command {
+    /// Returns the name of the command on the command line, or `None` for [`Command::Run`].
+    const fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Run => None,
+            Self::Lint => Some("lint"),
+            Self::Fmt => Some("fmt"),
+        }
+    }
+}
+
+/// Options passed to the interpreter on the command line.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub command: Command,
+    /// Path of the script to execute. Starts the REPL if missing.
+    pub script: Option<String>,
+    /// Only check whether the script is formatted, without rewriting it.
+    pub check: bool,
+    /// Run the script in the step debugger.
+    pub debug: bool,
+    /// Print every executed instruction.
+    pub trace: bool,
+    /// Write the trace to this file as JSON lines instead of printing it.
+    pub trace_file: Option<String>,
+    /// Resource limits for the script.
+    pub limits: Limits,
+    /// Seed for the random number generator.
+    pub seed: Option<u64>,
+    /// Maximum number of nested `CALL`s.
+    pub max_call_depth: Option<usize>,
+    /// Print usage information and exit.
+    pub help: bool,
+}
+
+pub const USAGE: &str = "\
+Usage: sasm-interpreter [OPTIONS] [SCRIPT]
+       sasm-interpreter lint SCRIPT
+       sasm-interpreter fmt [--check] SCRIPT
+
+Starts an interactive REPL if no script is given.
+`lint` checks the script for likely mistakes without executing it.
+`fmt` rewrites the script in its canonical format.
+
+Options:
+  --debug              Run the script in the interactive step debugger
+  --trace              Print every executed instruction and the variables it changed
+  --trace-file <PATH>  Write the trace to a file as JSON lines
+  --max-instructions <N>
+                       Stop the script after executing N instructions
+  --timeout <MS>       Stop the script after running for MS milliseconds
+  --max-string-memory <BYTES>
+                       Stop the script if its strings use more than BYTES bytes
+  --max-sleep <MS>     Stop the script if `HLT` tries to sleep for more than MS milliseconds
+  --max-call-depth <N> Maximum number of nested `CALL`s (default: 1024)
+  --seed <SEED>        Seed the random number generator to make `RNG` reproducible
+  --check              With `fmt`, fail if the script isn't formatted instead of rewriting it
+  --help               Print this message";
+
+impl Options {
+    /// Parses command line arguments _(without the program name)_.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--debug" => options.debug = true,
+                "--check" => options.check = true,
+                "--trace" => options.trace = true,
+                "--trace-file" => {
+                    options.trace = true;
+                    options.trace_file = Some(value_of(&arg, args.next())?);
+                }
+                "--max-instructions" => {
+                    options.limits.max_instructions = Some(number_of(&arg, args.next())?);
+                }
+                "--timeout" => {
+                    let ms = number_of(&arg, args.next())?;
+                    options.limits.max_duration = Some(Duration::from_millis(ms));
+                }
+                "--max-string-memory" => {
+                    options.limits.max_string_memory = Some(number_of(&arg, args.next())?);
+                }
+                "--max-sleep" => {
+                    let ms = number_of(&arg, args.next())?;
+                    options.limits.max_sleep = Some(Duration::from_millis(ms));
+                }
+                "--max-call-depth" => {
+                    options.max_call_depth = Some(number_of(&arg, args.next())?);
+                }
+                "--seed" => options.seed = Some(number_of(&arg, args.next())?),
+                "--help" | "-h" => options.help = true,
+                flag if flag.starts_with('-') => {
+                    return Err(format!("Unknown option `{flag}`\n\n{USAGE}"));
+                }
+                "lint" if options.script.is_none() && options.command == Command::Run => {
+                    options.command = Command::Lint;
+                }
+                "fmt" if options.script.is_none() && options.command == Command::Run => {
+                    options.command = Command::Fmt;
+                }
+                _ if options.script.is_some() => {
+                    return Err(format!("Unexpected argument `{arg}`\n\n{USAGE}"));
+                }
+                _ => options.script = Some(arg),
+            }
+        }
+
+        if let Some(name) = options.command.name() {
+            if options.script.is_none() {
+                return Err(format!("`{name}` requires a script\n\n{USAGE}"));
+            }
+
+            if options.debug || options.trace {
+                return Err(format!(
+                    "`{name}` can't be combined with `--debug` or `--trace`\n\n{USAGE}"
+                ));
+            }
+        }
+
+        if options.check && options.command != Command::Fmt {
+            return Err(format!("`--check` can only be used with `fmt`\n\n{USAGE}"));
+        }
+
+        if options.script.is_none() && (options.debug || options.trace) {
+            return Err(format!(
+                "`--debug` and `--trace` require a script\n\n{USAGE}"
+            ));
+        }
+
+        if options.debug && options.trace {
+            return Err(format!(
+                "`--debug` can't be combined with `--trace`\n\n{USAGE}"
+            ));
+        }
+
+        if options.debug && options.limits != Limits::default() {
+            return Err(format!(
+                "`--debug` can't be combined with resource limits\n\n{USAGE}"
+            ));
+        }
+
+        Ok(options)
+    }
+
+    /// Applies the options which don't depend on how the script is run.
+    pub fn configure<I: IoBackend>(&self, interpreter: &mut Interpreter<I>) {
+        if let Some(seed) = self.seed {
+            interpreter.set_seed(seed);
+        }
+
+        if let Some(depth) = self.max_call_depth {
+            interpreter.set_max_call_depth(depth);
+        }
+    }
+}
+
+fn value_of(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("Missing value for `{flag}`\n\n{USAGE}"))
+}
+
+fn number_of<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value_of(flag, value)?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value `{value}` for `{flag}`\n\n{USAGE}"))
+}
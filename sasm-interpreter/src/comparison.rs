@@ -0,0 +1,162 @@
+use sasm_parse::{
+    expression::{Expression, Float, Number},
+    Instruction,
+};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+};
+
+/// Result of the last `CMP` instruction.
+///
+/// Numbers and floats are compared by value, strings and lists are compared lexicographically.
+/// Maps can only be equal. Values of other types, as well as `NaN`s, can't be ordered.
+///
+/// ```rust
+/// # use sasm_interpreter::comparison::Comparison;
+/// # use sasm_parse::expression::Expression;
+/// assert_eq!(Comparison::of(&Expression::Number(1), &Expression::Float(1.5)), Comparison::Less);
+/// assert_eq!(
+///     Comparison::of(&Expression::make_string("b"), &Expression::make_string("a")),
+///     Comparison::Greater
+/// );
+/// assert_eq!(
+///     Comparison::of(&Expression::Number(1), &Expression::make_string("1")),
+///     Comparison::Unordered
+/// );
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    Equal,
+    Greater,
+    /// The values can't be compared, or nothing has been compared yet.
+    #[default]
+    Unordered,
+}
+
+impl Comparison {
+    /// Compares two values.
+    #[must_use]
+    pub fn of(first: &Expression, second: &Expression) -> Self {
+        match ordering(first, second) {
+            Some(Ordering::Less) => Self::Less,
+            Some(Ordering::Equal) => Self::Equal,
+            Some(Ordering::Greater) => Self::Greater,
+            None => Self::Unordered,
+        }
+    }
+
+    /// Returns whether a jump instruction _(including `CALL` and `RET`)_ jumps after this comparison,
+    /// or `None` for other instructions.
+    #[must_use]
+    pub const fn jump_taken(self, instr: &Instruction) -> Option<bool> {
+        let taken = match instr {
+            Instruction::Jump(..) | Instruction::Call(..) | Instruction::Return => true,
+            Instruction::JumpEqual(..) => matches!(self, Self::Equal),
+            Instruction::JumpNotEqual(..) => !matches!(self, Self::Equal),
+            Instruction::JumpLess(..) => matches!(self, Self::Less),
+            Instruction::JumpGreater(..) => matches!(self, Self::Greater),
+            Instruction::JumpLessEqual(..) => matches!(self, Self::Less | Self::Equal),
+            Instruction::JumpGreaterEqual(..) => matches!(self, Self::Greater | Self::Equal),
+            _ => return None,
+        };
+
+        Some(taken)
+    }
+}
+
+/// Lists are compared element by element, and then by their length.
+fn ordering(first: &Expression, second: &Expression) -> Option<Ordering> {
+    match (first, second) {
+        (Expression::Number(a), Expression::Number(b)) => Some(a.cmp(b)),
+        (Expression::String(a), Expression::String(b)) => Some(a.cmp(b)),
+        (Expression::Float(a), Expression::Float(b)) => a.partial_cmp(b),
+        (Expression::Number(a), Expression::Float(b)) => number_float_ordering(*a, *b),
+        (Expression::Float(a), Expression::Number(b)) => {
+            number_float_ordering(*b, *a).map(Ordering::reverse)
+        }
+        (Expression::List(a), Expression::List(b)) => {
+            for (a, b) in a.iter().zip(b.iter()) {
+                match ordering(a, b)? {
+                    Ordering::Equal => (),
+                    other => return Some(other),
+                }
+            }
+
+            Some(a.len().cmp(&b.len()))
+        }
+        (Expression::Map(a), Expression::Map(b)) if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+/// Compares exactly, casting the number to a float would round it above 2^24.
+fn number_float_ordering(num: Number, float: Float) -> Option<Ordering> {
+    // 2^63 is exactly representable, unlike `Number::MAX`
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    let float = f64::from(float);
+
+    if float.is_nan() {
+        return None;
+    }
+    if float >= LIMIT {
+        return Some(Ordering::Less);
+    }
+    if float < -LIMIT {
+        return Some(Ordering::Greater);
+    }
+
+    // The floor is within range, so the cast is exact
+    let floor = float.floor();
+    match num.cmp(&(floor as Number)) {
+        Ordering::Equal if float > floor => Some(Ordering::Less),
+        other => Some(other),
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Less => "less",
+            Self::Equal => "equal",
+            Self::Greater => "greater",
+            Self::Unordered => "unordered",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_and_floats_compare_exactly() {
+        // 2^24 + 1 rounds to 2^24 as a float
+        let big = 16_777_217;
+        let cases = [
+            (big, 16_777_216.0, Comparison::Greater),
+            (big - 1, 16_777_216.0, Comparison::Equal),
+            (-3, -2.5, Comparison::Less),
+            (-2, -2.5, Comparison::Greater),
+            (Number::MAX, 9.223_372e18, Comparison::Less),
+            (Number::MIN, -9.223_372e18, Comparison::Equal),
+            (Number::MIN, Float::NEG_INFINITY, Comparison::Greater),
+            (0, Float::NAN, Comparison::Unordered),
+        ];
+
+        for (num, float, expected) in cases {
+            let (num, float) = (Expression::Number(num), Expression::Float(float));
+            assert_eq!(Comparison::of(&num, &float), expected, "{num} {float}");
+
+            let reversed = match expected {
+                Comparison::Less => Comparison::Greater,
+                Comparison::Greater => Comparison::Less,
+                other => other,
+            };
+            assert_eq!(Comparison::of(&float, &num), reversed, "{float} {num}");
+        }
+    }
+}
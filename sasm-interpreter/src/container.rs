@@ -0,0 +1,326 @@
+use crate::{error::RuntimeError, strings};
+use sasm_parse::{
+    expression::{Expression, List, Map, Number, Text},
+    type_trait::SasmType,
+};
+use std::rc::Rc;
+
+/// Checks whether `value` can be stored in a list or map. The type of the elements is fixed by the
+/// first element, so a value can be stored if it has the same type, or if the container is empty.
+///
+/// Elements of nested lists and maps are compared too, an empty list or map matches any other.
+/// Variables remember the element type of containers which were emptied later, see
+/// [`VariableStorage::set`](crate::varstorage::VariableStorage::set).
+pub fn check_element(first: Option<&Expression>, value: &Expression) -> Result<(), RuntimeError> {
+    match first.and_then(|first| mismatch(first, value)) {
+        Some((expected, got)) => Err(RuntimeError::MismatchedTypes { got, expected }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the names of the innermost types that differ between two values, if any.
+fn mismatch(lhs: &Expression, rhs: &Expression) -> Option<(&'static str, &'static str)> {
+    if !lhs.cmp_type(rhs) {
+        return Some((lhs.type_name(), rhs.type_name()));
+    }
+
+    match (lhs, rhs) {
+        (Expression::List(lhs), Expression::List(rhs)) => mismatch(lhs.first()?, rhs.first()?),
+        (Expression::Map(lhs), Expression::Map(rhs)) => {
+            mismatch(lhs.values().next()?, rhs.values().next()?)
+        }
+        _ => None,
+    }
+}
+
+/// Converts an index into a position inside a list of length `len`.
+/// If `allow_end` is `true`, `len` itself is also accepted _(for inserting at the end)_.
+pub fn position(index: &Expression, len: usize, allow_end: bool) -> Result<usize, RuntimeError> {
+    let index = index_of(index)?;
+    let end = if allow_end { len + 1 } else { len };
+
+    usize::try_from(index)
+        .ok()
+        .filter(|pos| *pos < end)
+        .ok_or(RuntimeError::IndexOutOfBounds { index, len })
+}
+
+/// Returns the element at an index of a list, the value of a key in a map, or a character of a string.
+pub fn get(container: &Expression, key: &Expression) -> Result<Expression, RuntimeError> {
+    match container {
+        Expression::String(text) => strings::char_at(text, key),
+        Expression::List(list) => Ok(list[position(key, list.len(), false)?].clone()),
+        Expression::Map(map) => {
+            let key = key_of(key)?;
+            map.get(key.as_str())
+                .cloned()
+                .ok_or_else(|| RuntimeError::MissingKey(key.to_string()))
+        }
+        other => Err(RuntimeError::UnsizedObj(other.type_name())),
+    }
+}
+
+/// Replaces the element at an index of a list, or inserts a key into a map.
+/// Returns the updated container.
+pub fn set(
+    container: &Expression,
+    key: &Expression,
+    value: Expression,
+) -> Result<Expression, RuntimeError> {
+    match container {
+        Expression::List(list) => {
+            let pos = position(key, list.len(), false)?;
+            check_element(list.first(), &value)?;
+
+            let mut list = List::clone(list);
+            Rc::make_mut(&mut list)[pos] = value;
+            Ok(Expression::List(list))
+        }
+        Expression::Map(map) => {
+            let key = key_of(key)?;
+            check_element(map.values().next(), &value)?;
+
+            let mut map = Map::clone(map);
+            Rc::make_mut(&mut map).insert(key.to_string(), value);
+            Ok(Expression::Map(map))
+        }
+        other => Err(RuntimeError::UnsizedObj(other.type_name())),
+    }
+}
+
+/// Removes the element at an index of a list, or a key from a map.
+/// Returns the updated container and the removed value.
+pub fn remove(
+    container: &Expression,
+    key: &Expression,
+) -> Result<(Expression, Expression), RuntimeError> {
+    match container {
+        Expression::List(list) => {
+            let pos = position(key, list.len(), false)?;
+
+            let mut list = List::clone(list);
+            let removed = Rc::make_mut(&mut list).remove(pos);
+            Ok((Expression::List(list), removed))
+        }
+        Expression::Map(map) => {
+            let key = key_of(key)?;
+
+            let mut map = Map::clone(map);
+            let removed = Rc::make_mut(&mut map)
+                .remove(key.as_str())
+                .ok_or_else(|| RuntimeError::MissingKey(key.to_string()))?;
+            Ok((Expression::Map(map), removed))
+        }
+        other => Err(RuntimeError::UnsizedObj(other.type_name())),
+    }
+}
+
+fn index_of(index: &Expression) -> Result<Number, RuntimeError> {
+    match index {
+        Expression::Number(index) => Ok(*index),
+        other => Err(RuntimeError::MismatchedTypes {
+            got: other.type_name(),
+            expected: Number::type_name(),
+        }),
+    }
+}
+
+fn key_of(key: &Expression) -> Result<&Text, RuntimeError> {
+    match key {
+        Expression::String(key) => Ok(key),
+        other => Err(RuntimeError::MismatchedTypes {
+            got: other.type_name(),
+            expected: Text::type_name(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_element, get, position, remove, set};
+    use crate::{
+        comparison::Comparison,
+        error::RuntimeError,
+        testing::{error, output, scripted},
+    };
+    use sasm_parse::expression::Expression;
+    use std::rc::Rc;
+
+    #[test]
+    fn list_positions() {
+        let n = Expression::Number;
+
+        assert_eq!(position(&n(2), 3, false).unwrap(), 2);
+        assert_eq!(position(&n(3), 3, true).unwrap(), 3);
+        assert!(matches!(
+            position(&n(3), 3, false),
+            Err(RuntimeError::IndexOutOfBounds { index: 3, len: 3 })
+        ));
+        assert!(matches!(
+            position(&n(-1), 3, true),
+            Err(RuntimeError::IndexOutOfBounds { index: -1, len: 3 })
+        ));
+        assert!(matches!(
+            position(&Expression::make_string("0"), 3, false),
+            Err(RuntimeError::MismatchedTypes { .. })
+        ));
+    }
+
+    #[test]
+    fn list_access() {
+        let n = Expression::Number;
+        let list = Expression::List(Rc::new(vec![n(1), n(2)]));
+
+        assert_eq!(get(&list, &n(1)).unwrap(), n(2));
+
+        let updated = set(&list, &n(0), n(5)).unwrap();
+        assert_eq!(updated, Expression::List(Rc::new(vec![n(5), n(2)])));
+        assert!(set(&list, &n(0), Expression::make_string("a")).is_err());
+
+        let (updated, removed) = remove(&updated, &n(1)).unwrap();
+        assert_eq!(updated, Expression::List(Rc::new(vec![n(5)])));
+        assert_eq!(removed, n(2));
+        assert!(matches!(
+            get(&n(1), &n(0)),
+            Err(RuntimeError::UnsizedObj("Number"))
+        ));
+    }
+
+    #[test]
+    fn map_access() {
+        let key = Expression::make_string;
+        let map = set(&Expression::empty_map(), &key("a"), Expression::Number(1)).unwrap();
+
+        assert_eq!(get(&map, &key("a")).unwrap(), Expression::Number(1));
+        assert!(matches!(
+            get(&map, &key("b")),
+            Err(RuntimeError::MissingKey(key)) if key == "b"
+        ));
+        assert!(matches!(
+            get(&map, &Expression::Number(0)),
+            Err(RuntimeError::MismatchedTypes { .. })
+        ));
+        assert!(set(&map, &key("b"), Expression::Float(1.0)).is_err());
+
+        let (updated, removed) = remove(&map, &key("a")).unwrap();
+        assert_eq!(updated, Expression::empty_map());
+        assert_eq!(removed, Expression::Number(1));
+        assert!(remove(&updated, &key("a")).is_err());
+    }
+
+    #[test]
+    fn nested_element_types() {
+        let list = |elements: Vec<Expression>| Expression::List(Rc::new(elements));
+        let numbers = list(vec![Expression::Number(1)]);
+        let strings = list(vec![Expression::make_string("a")]);
+
+        assert!(check_element(Some(&numbers), &list(vec![Expression::Number(2)])).is_ok());
+        assert!(check_element(Some(&numbers), &Expression::empty_list()).is_ok());
+        assert!(check_element(Some(&list(vec![numbers.clone()])), &list(vec![])).is_ok());
+        assert!(matches!(
+            check_element(Some(&numbers), &strings),
+            Err(RuntimeError::MismatchedTypes {
+                got: "String",
+                expected: "Number"
+            })
+        ));
+        assert!(matches!(
+            check_element(Some(&list(vec![numbers])), &list(vec![strings])),
+            Err(RuntimeError::MismatchedTypes {
+                got: "String",
+                expected: "Number"
+            })
+        ));
+        assert!(check_element(Some(&Expression::empty_map()), &Expression::empty_list()).is_err());
+    }
+
+    #[test]
+    fn push_and_insert() {
+        assert_eq!(
+            output("VAR xs\nLST xs\nPSH xs,1\nPSH xs,2\nINS xs,0,0\nDMP xs"),
+            "[0, 1, 2]\n"
+        );
+    }
+
+    #[test]
+    fn set_and_remove() {
+        assert_eq!(
+            output(
+                "VAR xs\nLST xs\nPSH xs,1\nPSH xs,2\nSET xs,1,5\nVAR x\nRMV xs,0,x\nDMP xs\nDMP x"
+            ),
+            "[5]\n1\n"
+        );
+    }
+
+    #[test]
+    fn list_element_type() {
+        assert!(matches!(
+            error("VAR xs\nLST xs\nPSH xs,1\nPSH xs,\"a\""),
+            RuntimeError::MismatchedTypes {
+                got: "String",
+                expected: "Number"
+            }
+        ));
+    }
+
+    #[test]
+    fn nested_list_element_type() {
+        let mut interpreter = scripted(
+            "VAR a\nLST a\nPSH a,1\nVAR b\nLST b\nPSH b,\"x\"\nVAR xs\nLST xs\nPSH xs,a\nPSH xs,b",
+        );
+
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::MismatchedTypes {
+                got: "String",
+                expected: "Number"
+            })
+        ));
+        assert_eq!(interpreter.position(), 9);
+    }
+
+    #[test]
+    fn insert_out_of_bounds() {
+        assert!(matches!(
+            error("VAR xs\nLST xs\nINS xs,1,0"),
+            RuntimeError::IndexOutOfBounds { index: 1, len: 0 }
+        ));
+    }
+
+    #[test]
+    fn map_keys_are_sorted() {
+        assert_eq!(
+            output("VAR m\nMAP m\nSET m,\"b\",2\nSET m,\"a\",1\nVAR ks\nKEY ks,m\nDMP ks\nDMP m"),
+            "[\"a\", \"b\"]\n{\"a\": 1, \"b\": 2}\n"
+        );
+    }
+
+    #[test]
+    fn has_key_sets_flag() {
+        let mut interpreter = scripted("VAR m\nMAP m\nSET m,\"a\",1\nHAS m,\"a\"");
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.cmp_result(), Comparison::Equal);
+
+        let mut interpreter = scripted("VAR m\nMAP m\nHAS m,\"a\"");
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.cmp_result(), Comparison::Unordered);
+    }
+
+    #[test]
+    fn remove_from_map() {
+        assert_eq!(
+            output(
+                "VAR m\nMAP m\nSET m,\"a\",1\nSET m,\"b\",2\nVAR x\nRMV m,\"a\",x\nDMP m\nDMP x"
+            ),
+            "{\"b\": 2}\n1\n"
+        );
+    }
+
+    #[test]
+    fn missing_map_key() {
+        assert!(matches!(
+            error("VAR m\nMAP m\nVAR x\nGET x,m,\"a\""),
+            RuntimeError::MissingKey(key) if key == "a"
+        ));
+    }
+}
@@ -0,0 +1,419 @@
+use crate::{cli::Options, script_runner::format_runtime_err};
+use sasm_interpreter::{
+    display::quoted,
+    io::{IoBackend, StdIo},
+    Interpreter, Status,
+};
+use sasm_parse::{expression::Expression, ident::Identifier, program::Program};
+use std::{collections::BTreeSet, fmt::Write as _, process::exit};
+
+const HELP: &str = "\
+Commands:
+  s, step               Execute a single instruction
+  c, continue           Run until a breakpoint is reached or the program exits
+  b, break [LINE|LABEL] Set a breakpoint, or list all breakpoints
+  d, delete LINE|LABEL  Remove a breakpoint
+  p, print [VAR]        Print a variable, or all variables
+  set VAR VALUE         Change the value of a variable
+  f, flag               Show the result of the last comparison
+  l, list [N]           Show N lines of source around the current instruction
+  h, help               Show this message
+  q, quit               Stop debugging";
+
+/// Number of lines shown above and below the current line by default.
+const LIST_CONTEXT: usize = 3;
+
+/// The debugger shares the input and output of the program being debugged.
+struct Debugger<I: IoBackend = StdIo> {
+    interpreter: Interpreter<I>,
+    /// Indexes of instructions to stop at.
+    breakpoints: BTreeSet<usize>,
+}
+
+pub fn start(program: Program, options: &Options) {
+    let mut debugger = Debugger::new(Interpreter::new(program));
+
+    options.configure(&mut debugger.interpreter);
+
+    debugger.say("SASM Debugger");
+    debugger.say("Type `help` for a list of commands.\n");
+    debugger.list(LIST_CONTEXT);
+
+    loop {
+        // Nothing else can be done if the prompt can't be written
+        let _ = debugger.interpreter.io_mut().write("(debug) ");
+
+        let Ok(Some(line)) = debugger.interpreter.io_mut().read_line() else {
+            break;
+        };
+        let (command, arg) = line
+            .trim()
+            .split_once(' ')
+            .map_or((line.trim(), ""), |(cmd, arg)| (cmd, arg.trim()));
+
+        match command {
+            "" => (),
+            "s" | "step" => debugger.step(),
+            "c" | "continue" => debugger.resume(),
+            "b" | "break" => debugger.add_breakpoint(arg),
+            "d" | "delete" => debugger.remove_breakpoint(arg),
+            "p" | "print" => debugger.print(arg),
+            "set" => debugger.set(arg),
+            "f" | "flag" => {
+                let flag = debugger.interpreter.cmp_result();
+                debugger.say(format!("Last comparison: {flag}"));
+            }
+            "l" | "list" => match arg {
+                "" => debugger.list(LIST_CONTEXT),
+                n => match n.parse() {
+                    Ok(n) => debugger.list(n),
+                    Err(..) => debugger.warn(format!("Invalid number of lines: `{n}`")),
+                },
+            },
+            "h" | "help" => debugger.say(HELP),
+            "q" | "quit" => break,
+            other => debugger.warn(format!(
+                "Unknown command `{other}`, type `help` for a list of commands"
+            )),
+        }
+    }
+
+    exit(debugger.interpreter.exit_code().unwrap_or_default() as i32);
+}
+
+impl<I: IoBackend> Debugger<I> {
+    const fn new(interpreter: Interpreter<I>) -> Self {
+        Self {
+            interpreter,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Writes a line to the output.
+    fn say(&mut self, text: impl AsRef<str>) {
+        // Nothing else can be done if the output can't be written
+        let _ = self
+            .interpreter
+            .io_mut()
+            .write(&format!("{}\n", text.as_ref()));
+    }
+
+    /// Writes a line to the error output.
+    fn warn(&mut self, text: impl AsRef<str>) {
+        let _ = self
+            .interpreter
+            .io_mut()
+            .write_err(&format!("{}\n", text.as_ref()));
+    }
+
+    fn step(&mut self) {
+        if self.run_one() {
+            self.list(0);
+        }
+    }
+
+    fn resume(&mut self) {
+        while self.run_one() {
+            if self.breakpoints.contains(&self.interpreter.position()) {
+                self.say("Breakpoint reached");
+                self.list(LIST_CONTEXT);
+                break;
+            }
+        }
+    }
+
+    /// Executes one instruction. Returns `true` if the program can continue.
+    fn run_one(&mut self) -> bool {
+        match self.interpreter.step() {
+            Ok(Status::Running) => true,
+            Ok(Status::Exited(code)) => {
+                self.say(format!("Program exited with code {code}"));
+                false
+            }
+            Err(why) => {
+                let instr = self
+                    .interpreter
+                    .current()
+                    .expect("runtime errors are caused by an instruction");
+
+                let report = format_runtime_err(
+                    self.interpreter.program(),
+                    instr,
+                    &why,
+                    self.interpreter.call_stack(),
+                );
+
+                let _ = self.interpreter.io_mut().write_err(&report);
+                false
+            }
+        }
+    }
+
+    /// Finds the instruction at a line number, or the instruction a label points to.
+    fn resolve_location(&self, location: &str) -> Option<usize> {
+        let program = self.interpreter.program();
+
+        if let Ok(line) = location.parse::<usize>() {
+            return program
+                .instructions()
+                .iter()
+                .position(|instr| instr.span.line() >= line);
+        }
+
+        let label = Identifier::try_from(location).ok()?;
+        program
+            .labels()
+            .get(&label)
+            .filter(|index| *index < program.len())
+    }
+
+    fn add_breakpoint(&mut self, location: &str) {
+        if location.is_empty() {
+            for index in self.breakpoints.clone() {
+                let line = self.line_of(index);
+                self.say(format!("Breakpoint at line {line}"));
+            }
+            return;
+        }
+
+        let Some(index) = self.resolve_location(location) else {
+            self.warn(format!("No instruction found at `{location}`"));
+            return;
+        };
+
+        self.breakpoints.insert(index);
+        let line = self.line_of(index);
+        self.say(format!("Breakpoint set at line {line}"));
+    }
+
+    fn remove_breakpoint(&mut self, location: &str) {
+        let removed = self
+            .resolve_location(location)
+            .is_some_and(|index| self.breakpoints.remove(&index));
+
+        if !removed {
+            self.warn(format!("No breakpoint at `{location}`"));
+        }
+    }
+
+    /// Returns the source line of the instruction at an index.
+    fn line_of(&self, index: usize) -> usize {
+        self.interpreter.program().instructions()[index].span.line()
+    }
+
+    fn print(&mut self, name: &str) {
+        let variables = self.interpreter.variables();
+
+        if name.is_empty() {
+            let mut all: Vec<_> = variables.iter().collect();
+            all.sort_by_key(|(ident, _)| *ident);
+
+            let listing = all
+                .into_iter()
+                .fold(String::new(), |mut listing, (ident, value)| {
+                    let _ = writeln!(listing, "{ident} = {}", describe(value));
+                    listing
+                });
+            let _ = self.interpreter.io_mut().write(&listing);
+            return;
+        }
+
+        let value = Identifier::try_from(name)
+            .map_err(|why| why.to_string())
+            .and_then(|ident| variables.get(&ident).map_err(|why| why.to_string()));
+
+        match value {
+            Ok(value) => self.say(format!("{name} = {}", describe(value))),
+            Err(why) => self.warn(why),
+        }
+    }
+
+    /// Changes the value of a declared variable. The value is written like an instruction argument.
+    fn set(&mut self, arg: &str) {
+        let Some((name, value)) = arg.split_once(' ') else {
+            self.warn("Usage: set VAR VALUE");
+            return;
+        };
+
+        let ident = match Identifier::try_from(name) {
+            Ok(ident) => ident,
+            Err(why) => {
+                self.warn(why.to_string());
+                return;
+            }
+        };
+
+        // Setting an undeclared variable would declare it
+        if let Err(why) = self.interpreter.variables().get(&ident) {
+            self.warn(why.to_string());
+            return;
+        }
+
+        let value = match Expression::parse(value) {
+            Ok(Expression::Identifier(..)) => {
+                self.warn("Expected a number, float or string");
+                return;
+            }
+            Ok(value) => value,
+            Err(why) => {
+                self.warn(why.to_string());
+                return;
+            }
+        };
+
+        match self.interpreter.variables_mut().set(&ident, value) {
+            Ok(()) => self.print(name),
+            Err(why) => self.warn(why.to_string()),
+        }
+    }
+
+    /// Prints the source code around the current instruction.
+    fn list(&mut self, context: usize) {
+        let program = self.interpreter.program();
+        let Some(current) = self.interpreter.current() else {
+            self.say("Program has finished");
+            return;
+        };
+
+        let current_line = current.span.line();
+        let breakpoint_lines: BTreeSet<_> = self
+            .breakpoints
+            .iter()
+            .map(|index| program.instructions()[*index].span.line())
+            .collect();
+        let first = current_line.saturating_sub(context).max(1);
+        let last = (current_line + context).min(program.source_lines().len());
+        let mut listing = String::new();
+
+        for line in first..=last {
+            let marker = if line == current_line { "->" } else { "  " };
+            let breakpoint = if breakpoint_lines.contains(&line) {
+                '*'
+            } else {
+                ' '
+            };
+
+            let _ = writeln!(
+                listing,
+                "{breakpoint}{marker} {line:>4} | {}",
+                program.source_line(line).unwrap_or_default()
+            );
+        }
+
+        let _ = self.interpreter.io_mut().write(&listing);
+    }
+}
+
+fn describe(value: Option<&Expression>) -> String {
+    let Some(value) = value else {
+        return "null".into();
+    };
+
+    format!("{} ({})", quoted(value), value.type_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Debugger;
+    use sasm_interpreter::{io::MemoryIo, Interpreter};
+    use sasm_parse::{expression::Expression, ident::Identifier, program::Program};
+
+    const COUNTER: &str = "VAR x\nMOV x,0\nloop:\nINC x\nCMP x,3\nJNE loop\nDMP x";
+
+    fn debugger(source: &str) -> Debugger<MemoryIo> {
+        let program = Program::parse(source).unwrap();
+
+        Debugger::new(Interpreter::with_io(program, MemoryIo::default()))
+    }
+
+    fn value(debugger: &Debugger<MemoryIo>, name: &str) -> Option<Expression> {
+        let ident = Identifier::try_from(name).unwrap();
+
+        debugger
+            .interpreter
+            .variables()
+            .get(&ident)
+            .unwrap()
+            .cloned()
+    }
+
+    #[test]
+    fn resolve_breakpoints() {
+        let debugger = debugger(COUNTER);
+
+        // A line without an instruction resolves to the next instruction
+        assert_eq!(debugger.resolve_location("3"), Some(2));
+        assert_eq!(debugger.resolve_location("4"), Some(2));
+        assert_eq!(debugger.resolve_location("loop"), Some(2));
+        assert_eq!(debugger.resolve_location("7"), Some(5));
+        assert_eq!(debugger.resolve_location("8"), None);
+        assert_eq!(debugger.resolve_location("nowhere"), None);
+    }
+
+    #[test]
+    fn stop_at_breakpoints() {
+        let mut debugger = debugger(COUNTER);
+
+        debugger.add_breakpoint("loop");
+        debugger.resume();
+        assert_eq!(debugger.interpreter.position(), 2);
+        assert_eq!(value(&debugger, "x"), Some(Expression::Number(0)));
+
+        debugger.resume();
+        assert_eq!(debugger.interpreter.position(), 2);
+        assert_eq!(value(&debugger, "x"), Some(Expression::Number(1)));
+
+        debugger.remove_breakpoint("4");
+        assert!(debugger.breakpoints.is_empty());
+        debugger.resume();
+
+        let output = debugger.interpreter.io_mut().output();
+        assert!(
+            output.ends_with("3\nProgram exited with code 0\n"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn step_instructions() {
+        let mut debugger = debugger("VAR x\nMOV x,1\nDMP x");
+
+        debugger.step();
+        debugger.step();
+        assert_eq!(debugger.interpreter.position(), 2);
+        assert_eq!(value(&debugger, "x"), Some(Expression::Number(1)));
+        assert!(debugger
+            .interpreter
+            .io_mut()
+            .output()
+            .ends_with("->    3 | DMP x\n"));
+
+        debugger.step();
+        debugger.step();
+        assert_eq!(debugger.interpreter.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn set_variables() {
+        let mut debugger = debugger("VAR s\nMOV s,\"\"\nDMP s");
+        debugger.step();
+        debugger.step();
+
+        debugger.set(r#"s "a\n""#);
+        assert_eq!(value(&debugger, "s"), Some(Expression::make_string("a\n")));
+
+        debugger.set("s 1");
+        debugger.set("t \"b\"");
+        debugger.set("s \"a\"\"b\"");
+        assert_eq!(value(&debugger, "s"), Some(Expression::make_string("a\n")));
+        assert!(debugger
+            .interpreter
+            .variables()
+            .get(&Identifier::try_from("t").unwrap())
+            .is_err());
+        assert_eq!(
+            debugger.interpreter.io_mut().error_output().lines().count(),
+            3
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use sasm_parse::expression::Expression;
+
+/// Converts a value into the text printed by `DMP` and inserted by `FMT`.
+///
+/// ```rust
+/// # use sasm_interpreter::display::display;
+/// # use sasm_parse::expression::Expression;
+/// # use std::rc::Rc;
+/// let list = Expression::List(Rc::new(vec![
+///     Expression::make_string("a"),
+///     Expression::make_string("b"),
+/// ]));
+///
+/// assert_eq!(display(&Expression::make_string("a")), "a");
+/// assert_eq!(display(&list), "[\"a\", \"b\"]");
+/// ```
+#[must_use]
+pub fn display(value: &Expression) -> String {
+    match value {
+        Expression::String(text) => String::clone(text),
+        other => quoted(other),
+    }
+}
+
+/// Like [`display`], but strings are quoted and escaped.
+#[must_use]
+pub fn quoted(value: &Expression) -> String {
+    match value {
+        Expression::Number(n) => n.to_string(),
+        Expression::Float(v) => v.to_string(),
+        Expression::String(text) => format!("{text:?}"),
+        Expression::List(list) => {
+            let elements: Vec<_> = list.iter().map(quoted).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        Expression::Map(map) => {
+            let entries: Vec<_> = map
+                .iter()
+                .map(|(key, value)| format!("{key:?}: {}", quoted(value)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Expression::Identifier(..) => unreachable!(),
+    }
+}
@@ -1,11 +1,20 @@
-use sasm_parse::ident::Identifier;
+use crate::limits::Limit;
+use sasm_parse::{expression::Number, ident::Identifier};
 use std::{io, num::TryFromIntError};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum RuntimeError {
-    #[error("Illegal jump to line {0}")]
-    IllegalGoto(usize),
+    #[error("Illegal jump to instruction #{0}")]
+    IllegalGoto(isize),
+    #[error("Undefined label '{0}'")]
+    UndefinedLabel(Identifier),
+    #[error("Jumps are not supported outside of a program")]
+    UnsupportedJump,
+    #[error("Can't return, the call stack is empty")]
+    EmptyCallStack,
+    #[error("Maximum call depth of {0} exceeded")]
+    StackOverflow(usize),
     #[error("Variable '{0}' is internal and read-only")]
     IllegalWriteInternal(Identifier),
     #[error("Can't create variable named '{0}', which conflicts with internal variable naming")]
@@ -16,8 +25,12 @@ pub enum RuntimeError {
     DuplicateVarDef(Identifier),
     #[error("Variable does not have a value")]
     NullDeref,
-    #[error("This operation requires a variable of type 'Number'")]
+    #[error("This operation requires a variable of type 'Number' or 'Float'")]
     IllegalMathOp,
+    #[error("This operation requires a variable of type 'Float'")]
+    IllegalFloatOp,
+    #[error("Invalid exponent {0}, numbers can only be raised to non-negative powers")]
+    IllegalExponent(Number),
     #[error("Expected expression of type '{expected}', got '{got}'")]
     MismatchedTypes {
         got: &'static str,
@@ -27,10 +40,43 @@ pub enum RuntimeError {
     DivisionByZero,
     #[error("Expected array-like object, got {0}")]
     UnsizedObj(&'static str),
+    #[error("Index {index} is out of bounds for a list of length {len}")]
+    IndexOutOfBounds { index: Number, len: usize },
+    #[error("Key {0:?} not found")]
+    MissingKey(String),
     #[error("Failed to convert number types")]
     IntConversion(#[from] TryFromIntError),
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
+    #[error("Reached end of input")]
+    EndOfInput,
+    #[error("Execution limit exceeded: {0}")]
+    LimitExceeded(Limit),
+    #[error("Not enough memory for a string of {0} bytes")]
+    OutOfMemory(usize),
+    #[error("Invalid repeat count {0}")]
+    IllegalCount(Number),
+    #[error("Invalid range: minimum {0} is greater than maximum {1}")]
+    InvalidRange(Number, Number),
+    #[error("Can't convert {from} `{value}` to '{target}'")]
+    InvalidConversion {
+        value: String,
+        from: &'static str,
+        target: &'static str,
+    },
     #[error("Invalid number value: `{0}`")]
     IllegalNumber(String),
 }
+
+impl RuntimeError {
+    /// Returns the variable this error is about, if any.
+    pub const fn identifier(&self) -> Option<&Identifier> {
+        match self {
+            Self::IllegalWriteInternal(ident)
+            | Self::IllegalCreateInternal(ident)
+            | Self::UndefinedVar(ident)
+            | Self::DuplicateVarDef(ident) => Some(ident),
+            _ => None,
+        }
+    }
+}
@@ -1,28 +1,34 @@
-use crate::{error::RuntimeError, formatter::format, varstorage::VariableStorage};
+use crate::{
+    comparison::Comparison, container, display::display, error::RuntimeError, formatter::format,
+    io::IoBackend, limits::Limits, strings, varstorage::VariableStorage,
+};
+use fastrand::Rng;
 use sasm_parse::{
-    expression::{Expression, Number, Text},
+    expression::{Expression, Float, List, Map, Number, Text},
     ident::Identifier,
+    label::JumpTarget,
     type_trait::SasmType,
     Instruction,
 };
-use std::{
-    io::{stdin, stdout, Write},
-    process::exit,
-    rc::Rc,
-    thread::sleep,
-    time::Duration,
-};
+use std::{rc::Rc, time::Duration};
 
 pub enum ExecutorState {
     Ok,
-    Goto(isize),
+    Goto(JumpTarget),
+    Call(JumpTarget),
+    Return,
+    Sleep(Duration),
+    Exit(Number),
 }
 
 #[allow(clippy::too_many_lines)]
 pub fn execute(
     instr: &Instruction,
     vars: &mut VariableStorage,
-    cmp_result: &mut bool,
+    cmp_result: &mut Comparison,
+    io: &mut dyn IoBackend,
+    rng: &mut Rng,
+    limits: &Limits,
 ) -> Result<ExecutorState, RuntimeError> {
     match instr {
         Instruction::CreateVariable(ident) => {
@@ -35,47 +41,92 @@ pub fn execute(
             let value = pass_or_fetch(vars, src)?.clone();
             vars.set(dst, value)?;
         }
-        Instruction::Increment(ident) => single_step(ident, vars, |current| current + 1)?,
-        Instruction::Decrement(ident) => single_step(ident, vars, |current| current - 1)?,
-        Instruction::Dump(expr) => var_dump(pass_or_fetch_nullable(vars, expr)?),
-        Instruction::Add(ident, expr) => math_op(vars, ident, expr, Number::wrapping_add)?,
-        Instruction::Multiply(ident, expr) => math_op(vars, ident, expr, Number::wrapping_mul)?,
-        Instruction::Divide(ident, expr) => {
-            let amount = expect::<Number>(pass_or_fetch(vars, expr)?)?;
-
-            if amount == 0 {
-                return Err(RuntimeError::DivisionByZero);
-            }
-
-            single_step(ident, vars, |current| current / amount)?;
-        }
-        Instruction::Power(ident, expr) => {
-            let amount = expect::<Number>(pass_or_fetch(vars, expr)?)?;
-
-            single_step(ident, vars, |current| current.pow(amount as _))?;
-        }
-        Instruction::Subtract(ident, expr) => math_op(vars, ident, expr, Number::wrapping_sub)?,
+        Instruction::Increment(ident) => math_op(
+            vars,
+            ident,
+            &Expression::Number(1),
+            |a, b| Ok(a.wrapping_add(b)),
+            |a, b| a + b,
+        )?,
+        Instruction::Decrement(ident) => math_op(
+            vars,
+            ident,
+            &Expression::Number(1),
+            |a, b| Ok(a.wrapping_sub(b)),
+            |a, b| a - b,
+        )?,
+        Instruction::Dump(expr) => var_dump(pass_or_fetch_nullable(vars, expr)?, io)?,
+        Instruction::Add(ident, expr) => math_op(
+            vars,
+            ident,
+            expr,
+            |a, b| Ok(a.wrapping_add(b)),
+            |a, b| a + b,
+        )?,
+        Instruction::Multiply(ident, expr) => math_op(
+            vars,
+            ident,
+            expr,
+            |a, b| Ok(a.wrapping_mul(b)),
+            |a, b| a * b,
+        )?,
+        Instruction::Divide(ident, expr) => math_op(
+            vars,
+            ident,
+            expr,
+            |a, b| {
+                if b == 0 {
+                    return Err(RuntimeError::DivisionByZero);
+                }
+                Ok(a.wrapping_div(b))
+            },
+            |a, b| a / b,
+        )?,
+        Instruction::Power(ident, expr) => math_op(
+            vars,
+            ident,
+            expr,
+            |a, b| {
+                let exponent = u32::try_from(b).map_err(|_| RuntimeError::IllegalExponent(b))?;
+                Ok(a.wrapping_pow(exponent))
+            },
+            Float::powf,
+        )?,
+        Instruction::SquareRoot(ident) => float_op(vars, ident, Float::sqrt)?,
+        Instruction::Sine(ident) => float_op(vars, ident, Float::sin)?,
+        Instruction::Cosine(ident) => float_op(vars, ident, Float::cos)?,
+        Instruction::Logarithm(ident) => float_op(vars, ident, Float::ln)?,
+        Instruction::Floor(ident) => float_op(vars, ident, Float::floor)?,
+        Instruction::Ceil(ident) => float_op(vars, ident, Float::ceil)?,
+        Instruction::Round(ident) => float_op(vars, ident, Float::round)?,
+        Instruction::Subtract(ident, expr) => math_op(
+            vars,
+            ident,
+            expr,
+            |a, b| Ok(a.wrapping_sub(b)),
+            |a, b| a - b,
+        )?,
         Instruction::Compare(ident, expr) => {
-            let first = vars.get_nonnull(ident)?.clone();
+            let first = vars.get_nonnull(ident)?;
             let second = pass_or_fetch(vars, expr)?;
 
-            *cmp_result = &first == second;
-        }
-        Instruction::JumpEqual(offset) => {
-            if *cmp_result {
-                return Ok(ExecutorState::Goto(*offset as isize));
-            }
+            *cmp_result = Comparison::of(first, second);
         }
-        Instruction::JumpNotEqual(offset) => {
-            if !*cmp_result {
-                return Ok(ExecutorState::Goto(*offset as isize));
+        Instruction::Jump(target)
+        | Instruction::JumpEqual(target)
+        | Instruction::JumpNotEqual(target)
+        | Instruction::JumpLess(target)
+        | Instruction::JumpGreater(target)
+        | Instruction::JumpLessEqual(target)
+        | Instruction::JumpGreaterEqual(target) => {
+            if cmp_result.jump_taken(instr) == Some(true) {
+                return Ok(ExecutorState::Goto(target.clone()));
             }
         }
-        Instruction::Jump(offset) => {
-            return Ok(ExecutorState::Goto(*offset as isize));
-        }
+        Instruction::Call(target) => return Ok(ExecutorState::Call(target.clone())),
+        Instruction::Return => return Ok(ExecutorState::Return),
         Instruction::ReadNumericValue(ident) => {
-            let line = stdin_readline()?;
+            let line = readline(io)?;
 
             let Ok(num) = line.parse::<Number>() else {
                 return Err(RuntimeError::IllegalNumber(line));
@@ -84,7 +135,7 @@ pub fn execute(
             vars.set(ident, Expression::Number(num))?;
         }
         Instruction::ReadStringValue(ident) => {
-            vars.set(ident, Expression::make_string(stdin_readline()?))?;
+            vars.set(ident, Expression::make_string(readline(io)?))?;
         }
         Instruction::GenerateRandomNumber(ident, range_min, range_max) => {
             let mut min = Number::MIN;
@@ -96,23 +147,42 @@ pub fn execute(
                 max = expect(pass_or_fetch(vars, range_max)?)?;
             }
 
-            let randval = fastrand::i64(min..=max);
+            if min > max {
+                return Err(RuntimeError::InvalidRange(min, max));
+            }
+
+            let randval = rng.i64(min..=max);
             vars.set(ident, Expression::Number(randval))?;
         }
         Instruction::Push(ident, src) => {
-            let src = pass_or_fetch(vars, src)?;
-            let mut string = expect::<Text>(vars.get_nonnull(ident)?)?;
-            let mutable_string = Rc::make_mut(&mut string);
+            let src = pass_or_fetch(vars, src)?.clone();
 
-            match src {
-                Expression::String(other) => mutable_string.push_str(other),
-                Expression::Number(other) => mutable_string.push_str(&other.to_string()),
-                Expression::Float(other) => mutable_string.push_str(&other.to_string()),
-                Expression::Identifier(..) => unreachable!(),
+            if let Expression::List(list) = vars.get_nonnull(ident)? {
+                let mut list = List::clone(list);
+                container::check_element(list.first(), &src)?;
+
+                Rc::make_mut(&mut list).push(src);
+                vars.set(ident, Expression::List(list))?;
+                return Ok(ExecutorState::Ok);
             }
+
+            let mut string = expect::<Text>(vars.get_nonnull(ident)?)?;
+            Rc::make_mut(&mut string).push_str(&display(&src));
             vars.set(ident, Expression::rewrap_string(string))?;
         }
         Instruction::Pop(what, dst) => {
+            if let Expression::List(list) = vars.get_nonnull(what)? {
+                let mut list = List::clone(list);
+                let popped = Rc::make_mut(&mut list).pop();
+
+                if let Some((dst_ident, value)) = dst.as_ref().zip(popped) {
+                    vars.set(dst_ident, value)?;
+                }
+
+                vars.set(what, Expression::List(list))?;
+                return Ok(ExecutorState::Ok);
+            }
+
             let mut string = expect::<Text>(vars.get_nonnull(what)?)?;
             let mutable_string = Rc::make_mut(&mut string);
             let popped = mutable_string.pop();
@@ -123,15 +193,134 @@ pub fn execute(
 
             vars.set(what, Expression::rewrap_string(string))?;
         }
-        Instruction::Print(what) => {
-            match what {
-                Expression::Identifier(..) => panic!("Attempted to print an identifier"),
-                Expression::Number(n) => println!("{n}"),
-                Expression::Float(v) => println!("{v}"),
-                Expression::String(text) => print!("{text}"),
+        Instruction::CreateList(ident) => vars.set(ident, Expression::empty_list())?,
+        Instruction::CreateMap(ident) => vars.set(ident, Expression::empty_map())?,
+        Instruction::GetItem(dst, container, key) => {
+            let key = pass_or_fetch(vars, key)?;
+            let value = container::get(vars.get_nonnull(container)?, key)?;
+
+            vars.set(dst, value)?;
+        }
+        Instruction::SetItem(ident, key, value) => {
+            let key = pass_or_fetch(vars, key)?;
+            let value = pass_or_fetch(vars, value)?.clone();
+            let updated = container::set(vars.get_nonnull(ident)?, key, value)?;
+
+            vars.set(ident, updated)?;
+        }
+        Instruction::InsertItem(ident, index, value) => {
+            let value = pass_or_fetch(vars, value)?.clone();
+            let mut list = expect::<List>(vars.get_nonnull(ident)?)?;
+            let pos = container::position(pass_or_fetch(vars, index)?, list.len(), true)?;
+            container::check_element(list.first(), &value)?;
+
+            Rc::make_mut(&mut list).insert(pos, value);
+            vars.set(ident, Expression::List(list))?;
+        }
+        Instruction::RemoveItem(ident, key, dst) => {
+            let key = pass_or_fetch(vars, key)?;
+            let (updated, removed) = container::remove(vars.get_nonnull(ident)?, key)?;
+            vars.set(ident, updated)?;
+
+            if let Some(dst) = dst {
+                vars.set(dst, removed)?;
+            }
+        }
+        Instruction::HasKey(ident, key) => {
+            let map = expect::<Map>(vars.get_nonnull(ident)?)?;
+            let key = expect::<Text>(pass_or_fetch(vars, key)?)?;
+
+            *cmp_result = if map.contains_key(key.as_str()) {
+                Comparison::Equal
+            } else {
+                Comparison::Unordered
+            };
+        }
+        Instruction::Keys(dst, ident) => {
+            let map = expect::<Map>(vars.get_nonnull(ident)?)?;
+            let keys = map.keys().map(Expression::make_string).collect();
+
+            vars.set(dst, Expression::List(Rc::new(keys)))?;
+        }
+        Instruction::Print(what) => match pass_or_fetch(vars, what)? {
+            Expression::Identifier(..) => unreachable!(),
+            Expression::Number(n) => io.write(&format!("{n}\n"))?,
+            Expression::Float(v) => io.write(&format!("{v}\n"))?,
+            value => io.write(&display(value))?,
+        },
+        Instruction::IntoNumber(dst, src) => {
+            let value = into_number(pass_or_fetch(vars, src)?)?;
+            vars.set(dst, value)?;
+        }
+        Instruction::IntoFloat(dst, src) => {
+            let value = into_float(pass_or_fetch(vars, src)?)?;
+            vars.set(dst, value)?;
+        }
+        Instruction::IntoString(dst, src) => {
+            let text = display(pass_or_fetch(vars, src)?);
+            vars.set(dst, Expression::make_string(text))?;
+        }
+        Instruction::TypeOf(dst, src) => {
+            let type_name =
+                pass_or_fetch_nullable(vars, src)?.map_or("Null", Expression::type_name);
+            vars.set(dst, Expression::make_string(type_name))?;
+        }
+        Instruction::Substring(dst, src, start, end) => {
+            let text = expect::<Text>(pass_or_fetch(vars, src)?)?;
+            let start = pass_or_fetch(vars, start)?;
+            let end = end
+                .as_ref()
+                .map(|end| pass_or_fetch(vars, end))
+                .transpose()?;
+
+            let substring = strings::substring(&text, start, end)?;
+            vars.set(dst, Expression::make_string(substring))?;
+        }
+        Instruction::Find(dst, src, needle) => {
+            let text = expect::<Text>(pass_or_fetch(vars, src)?)?;
+            let needle = expect::<Text>(pass_or_fetch(vars, needle)?)?;
+
+            vars.set(dst, Expression::Number(strings::find(&text, &needle)?))?;
+        }
+        Instruction::Replace(ident, from, to) => {
+            let from = expect::<Text>(pass_or_fetch(vars, from)?)?;
+            let to = expect::<Text>(pass_or_fetch(vars, to)?)?;
+
+            text_op(vars, ident, |text| Ok(text.replace(from.as_str(), &to)))?;
+        }
+        Instruction::Uppercase(ident) => text_op(vars, ident, |text| Ok(text.to_uppercase()))?,
+        Instruction::Lowercase(ident) => text_op(vars, ident, |text| Ok(text.to_lowercase()))?,
+        Instruction::Trim(ident) => text_op(vars, ident, |text| Ok(text.trim().to_string()))?,
+        Instruction::Reverse(ident) => {
+            if let Expression::List(list) = vars.get_nonnull(ident)? {
+                let mut list = List::clone(list);
+                Rc::make_mut(&mut list).reverse();
+
+                vars.set(ident, Expression::List(list))?;
+                return Ok(ExecutorState::Ok);
             }
 
-            stdout().flush()?;
+            text_op(vars, ident, |text| Ok(text.chars().rev().collect()))?;
+        }
+        Instruction::Repeat(ident, count) => {
+            let count = expect::<Number>(pass_or_fetch(vars, count)?)?;
+            text_op(vars, ident, |text| {
+                strings::repeat(text, count, limits.max_string_memory)
+            })?;
+        }
+        Instruction::Split(dst, src, separator) => {
+            let text = expect::<Text>(pass_or_fetch(vars, src)?)?;
+            let separator = expect::<Text>(pass_or_fetch(vars, separator)?)?;
+
+            vars.set(dst, strings::split(&text, &separator))?;
+        }
+        Instruction::CharCode(dst, src) => {
+            let code = strings::char_code(pass_or_fetch(vars, src)?)?;
+            vars.set(dst, Expression::Number(code))?;
+        }
+        Instruction::CharFromCode(dst, code) => {
+            let code = expect::<Number>(pass_or_fetch(vars, code)?)?;
+            vars.set(dst, strings::char_from_code(code)?)?;
         }
         Instruction::Format(dst, fmt) => {
             let formatted = format(fmt, vars)?;
@@ -146,7 +335,13 @@ pub fn execute(
                     return Err(RuntimeError::UnsizedObj(value.type_name()))
                 }
                 Expression::String(s) => {
-                    vars.set(dst, Expression::Number(s.len().try_into()?))?;
+                    vars.set(dst, Expression::Number(s.chars().count().try_into()?))?;
+                }
+                Expression::List(list) => {
+                    vars.set(dst, Expression::Number(list.len().try_into()?))?;
+                }
+                Expression::Map(map) => {
+                    vars.set(dst, Expression::Number(map.len().try_into()?))?;
                 }
             }
         }
@@ -164,39 +359,91 @@ pub fn execute(
                 Expression::Float(..) => {
                     vars.set(what, Expression::zero_float())?;
                 }
+                Expression::List(..) => {
+                    vars.set(what, Expression::empty_list())?;
+                }
+                Expression::Map(..) => {
+                    vars.set(what, Expression::empty_map())?;
+                }
             }
         }
         Instruction::Sleep(time_expr) => {
             let ms = expect::<Number>(pass_or_fetch(vars, time_expr)?)?;
-            sleep(Duration::from_millis(ms.try_into()?));
+            return Ok(ExecutorState::Sleep(Duration::from_millis(ms.try_into()?)));
         }
         Instruction::Delete(ident) => {
             vars.delete(ident)?;
         }
-        Instruction::Die(code) => exit(*code as i32),
+        Instruction::Die(code) => return Ok(ExecutorState::Exit(*code)),
     }
 
     Ok(ExecutorState::Ok)
 }
 
-fn stdin_readline() -> Result<String, RuntimeError> {
-    let mut line = String::new();
-    stdin().read_line(&mut line)?;
-
-    line = line.trim_end().to_string();
-    Ok(line)
+fn readline(io: &mut dyn IoBackend) -> Result<String, RuntimeError> {
+    io.read_line()?.ok_or(RuntimeError::EndOfInput)
 }
 
-fn math_op<F: FnOnce(Number, Number) -> Number>(
+/// Applies an arithmetic operation to a variable. The result keeps the type of the variable:
+/// number variables only accept number operands, float variables accept both _(numbers are
+/// converted to floats)_.
+fn math_op<I, F>(
     vars: &mut VariableStorage,
     ident: &Identifier,
     expr: &Expression,
+    int_op: I,
+    float_op: F,
+) -> Result<(), RuntimeError>
+where
+    I: FnOnce(Number, Number) -> Result<Number, RuntimeError>,
+    F: FnOnce(Float, Float) -> Float,
+{
+    let operand = pass_or_fetch(vars, expr)?;
+
+    let result = match (vars.get_nonnull(ident)?, operand) {
+        (Expression::Number(current), Expression::Number(amount)) => {
+            Expression::Number(int_op(*current, *amount)?)
+        }
+        (Expression::Float(current), Expression::Float(amount)) => {
+            Expression::Float(float_op(*current, *amount))
+        }
+        (Expression::Float(current), Expression::Number(amount)) => {
+            Expression::Float(float_op(*current, *amount as Float))
+        }
+        (current @ (Expression::Number(..) | Expression::Float(..)), other) => {
+            return Err(RuntimeError::MismatchedTypes {
+                got: other.type_name(),
+                expected: current.type_name(),
+            })
+        }
+        _ => return Err(RuntimeError::IllegalMathOp),
+    };
+
+    vars.set(ident, result)
+}
+
+/// Applies a function to a variable containing a float.
+fn float_op<F: FnOnce(Float) -> Float>(
+    vars: &mut VariableStorage,
+    ident: &Identifier,
     op: F,
 ) -> Result<(), RuntimeError> {
-    let amount = expect(pass_or_fetch(vars, expr)?)?;
+    let Expression::Float(current) = vars.get_nonnull(ident)? else {
+        return Err(RuntimeError::IllegalFloatOp);
+    };
 
-    single_step(ident, vars, |current| op(current, amount))?;
-    Ok(())
+    vars.set(ident, Expression::Float(op(*current)))
+}
+
+/// Replaces the string inside a variable with the result of a function.
+fn text_op<F: FnOnce(&str) -> Result<String, RuntimeError>>(
+    vars: &mut VariableStorage,
+    ident: &Identifier,
+    op: F,
+) -> Result<(), RuntimeError> {
+    let text = expect::<Text>(vars.get_nonnull(ident)?)?;
+
+    vars.set(ident, Expression::make_string(op(&text)?))
 }
 
 pub fn pass_or_fetch<'a>(
@@ -219,19 +466,41 @@ pub fn pass_or_fetch_nullable<'a>(
     }
 }
 
-fn single_step<F: FnOnce(Number) -> Number>(
-    ident: &Identifier,
-    vars: &mut VariableStorage,
-    step: F,
-) -> Result<(), RuntimeError> {
-    let value_ref = vars.get_nonnull(ident)?;
+fn into_number(value: &Expression) -> Result<Expression, RuntimeError> {
+    let converted = match value {
+        Expression::Number(n) => Some(*n),
+        // Saturating casts would silently turn NaN into 0 and infinities into `Number::MAX`
+        Expression::Float(v) if v.is_finite() && v.abs() < Number::MAX as Float => {
+            Some(v.trunc() as Number)
+        }
+        Expression::String(text) => text.trim().parse().ok(),
+        _ => None,
+    };
 
-    let Expression::Number(current) = value_ref else {
-        return Err(RuntimeError::IllegalMathOp);
+    converted
+        .map(Expression::Number)
+        .ok_or_else(|| conversion_err(value, Number::type_name()))
+}
+
+fn into_float(value: &Expression) -> Result<Expression, RuntimeError> {
+    let converted = match value {
+        Expression::Number(n) => Some(*n as Float),
+        Expression::Float(v) => Some(*v),
+        Expression::String(text) => text.trim().parse().ok(),
+        _ => None,
     };
 
-    vars.set(ident, Expression::Number(step(*current)))?;
-    Ok(())
+    converted
+        .map(Expression::Float)
+        .ok_or_else(|| conversion_err(value, Float::type_name()))
+}
+
+fn conversion_err(value: &Expression, target: &'static str) -> RuntimeError {
+    RuntimeError::InvalidConversion {
+        value: display(value),
+        from: value.type_name(),
+        target,
+    }
 }
 
 fn expect<T: SasmType + 'static>(expr: &Expression) -> Result<T, RuntimeError> {
@@ -248,12 +517,103 @@ fn expect<T: SasmType + 'static>(expr: &Expression) -> Result<T, RuntimeError> {
     Ok(*res)
 }
 
-fn var_dump(expr: Option<&Expression>) {
-    match expr {
-        None => println!("null"),
-        Some(Expression::Number(n)) => println!("{n}"),
-        Some(Expression::String(s)) => println!("{s}"),
-        Some(Expression::Float(v)) => println!("{v}"),
-        Some(Expression::Identifier(..)) => unreachable!(),
+fn var_dump(expr: Option<&Expression>, io: &mut dyn IoBackend) -> Result<(), RuntimeError> {
+    let repr = expr.map_or_else(|| "null".to_string(), display);
+
+    io.write(&format!("{repr}\n"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::RuntimeError,
+        io::MemoryIo,
+        testing::{error, output},
+        Interpreter,
+    };
+    use sasm_parse::program::Program;
+
+    #[test]
+    fn float_arithmetic() {
+        assert_eq!(
+            output("VAR x\nMOV x,1.5\nADD x,2\nMUL x,2.0\nINC x\nDMP x"),
+            "8\n"
+        );
+    }
+
+    #[test]
+    fn float_functions() {
+        assert_eq!(
+            output("VAR x\nMOV x,8.0\nSQRT x\nDMP x\nMOV x,2.5\nROUND x\nDMP x"),
+            "2.828427\n3\n"
+        );
+    }
+
+    #[test]
+    fn integer_division_truncates() {
+        assert_eq!(output("VAR n\nMOV n,-7\nDIV n,2\nDMP n"), "-3\n");
+    }
+
+    #[test]
+    fn number_with_float_operand() {
+        assert!(matches!(
+            error("VAR n\nMOV n,7\nADD n,0.5"),
+            RuntimeError::MismatchedTypes { .. }
+        ));
+    }
+
+    #[test]
+    fn float_function_on_number() {
+        assert!(matches!(
+            error("VAR n\nMOV n,4\nSQRT n"),
+            RuntimeError::IllegalFloatOp
+        ));
+    }
+
+    #[test]
+    fn number_from_input() {
+        let program = Program::parse("VAR s\nRSV s\nVAR n\nNUM n,s\nINC n\nDMP n").unwrap();
+        let mut interpreter = Interpreter::with_io(program, MemoryIo::new([" 10 "]));
+
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.io().output(), "11\n");
+    }
+
+    #[test]
+    fn float_to_string() {
+        assert_eq!(
+            output("VAR f\nFLT f,10\nDIV f,4\nVAR s\nSTR s,f\nDMP s"),
+            "2.5\n"
+        );
+    }
+
+    #[test]
+    fn float_to_number_truncates() {
+        assert_eq!(output("VAR n\nNUM n,-2.9\nDMP n"), "-2\n");
+    }
+
+    #[test]
+    fn infinite_float_to_number() {
+        assert!(matches!(
+            error("VAR n\nNUM n,1e39"),
+            RuntimeError::InvalidConversion { from: "Float", .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_float() {
+        assert!(matches!(
+            error("VAR f\nFLT f,\"x\""),
+            RuntimeError::InvalidConversion { from: "String", .. }
+        ));
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(
+            output("VAR n\nVAR t\nTYPEOF t,n\nDMP t\nMOV n,1\nTYPEOF t,n\nDMP t"),
+            "Null\nNumber\n"
+        );
     }
 }
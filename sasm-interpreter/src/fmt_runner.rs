@@ -0,0 +1,39 @@
+use crate::report_parse_err;
+use sasm_parse::source_fmt::format_source;
+use std::fs;
+
+/// Formats a script in place. With `check`, the script is left unchanged and it's only reported
+/// whether it's formatted.
+/// Returns the exit code: `1` if the script can't be parsed or _(with `check`)_ isn't formatted.
+pub fn start(path: &str, check: bool) -> i32 {
+    let Ok(script) = fs::read_to_string(path) else {
+        eprintln!("Failed to read script file");
+        return 1;
+    };
+
+    let formatted = match format_source(&script, Some(path)) {
+        Ok(formatted) => formatted,
+        Err(errors) => {
+            for error in &errors {
+                report_parse_err(&script, error);
+            }
+            return 1;
+        }
+    };
+
+    if formatted == script {
+        return 0;
+    }
+
+    if check {
+        eprintln!("{path} is not formatted");
+        return 1;
+    }
+
+    if let Err(why) = fs::write(path, formatted) {
+        eprintln!("Failed to write script file: {why}");
+        return 1;
+    }
+
+    0
+}
@@ -1,29 +1,175 @@
-use crate::{error::RuntimeError, varstorage::VariableStorage};
-use regex::Regex;
-use sasm_parse::{expression::Expression, ident::Identifier};
-use std::sync::OnceLock;
+use crate::{display::display, error::RuntimeError, varstorage::VariableStorage};
+use sasm_parse::{
+    expression::{Expression, Number},
+    template::{Align, FormatKind, FormatSpec, Segment, Template},
+    type_trait::SasmType,
+};
 
-static PATTERN: OnceLock<Regex> = OnceLock::new();
+pub fn format(template: &Template, vars: &VariableStorage) -> Result<String, RuntimeError> {
+    let mut buffer = String::new();
 
-pub fn format(fmt: &str, vars: &VariableStorage) -> Result<String, RuntimeError> {
-    let mut buffer = fmt.to_string();
-    // TODO: Allow escaping
-    let re = PATTERN.get_or_init(|| Regex::new(r"\{[a-zA-Z]\w*\}").unwrap());
+    for segment in template.segments() {
+        match segment {
+            Segment::Literal(text) => buffer.push_str(text),
+            Segment::Placeholder(name, spec) => {
+                let value = vars.get_nonnull(name)?;
+                buffer.push_str(&format_value(value, spec)?);
+            }
+        }
+    }
 
-    while let Some(cap) = re.find(&buffer) {
-        let var_name = &cap.as_str()[1..(cap.len() - 1)];
-        let ident = Identifier::try_from(var_name).unwrap();
+    Ok(buffer)
+}
 
-        let var_value = vars.get_nonnull(&ident)?;
-        let repr = match var_value {
-            Expression::Number(n) => n.to_string(),
-            Expression::String(string) => String::clone(string),
-            Expression::Float(val) => val.to_string(),
-            Expression::Identifier(..) => unreachable!(),
-        };
+fn format_value(value: &Expression, spec: &FormatSpec) -> Result<String, RuntimeError> {
+    let text = match (spec.kind, value) {
+        (FormatKind::Display, Expression::Float(v)) => spec
+            .precision
+            .map_or_else(|| v.to_string(), |precision| format!("{v:.precision$}")),
+        (FormatKind::Display, Expression::String(text)) => spec.precision.map_or_else(
+            || text.to_string(),
+            |precision| text.chars().take(precision).collect(),
+        ),
+        (FormatKind::Display, other) => display(other),
+        (FormatKind::LowerHex, Expression::Number(n)) => format!("{n:x}"),
+        (FormatKind::UpperHex, Expression::Number(n)) => format!("{n:X}"),
+        (FormatKind::Binary, Expression::Number(n)) => format!("{n:b}"),
+        (_, other) => {
+            return Err(RuntimeError::MismatchedTypes {
+                got: other.type_name(),
+                expected: Number::type_name(),
+            })
+        }
+    };
+
+    let numeric = matches!(value, Expression::Number(..) | Expression::Float(..));
+    Ok(pad(text, spec, numeric))
+}
 
-        buffer.replace_range(cap.range(), &repr);
+/// Pads `text` to the width of `spec`. Numbers are aligned right by default, and zero-padding
+/// inserts the zeros after the sign.
+fn pad(text: String, spec: &FormatSpec, numeric: bool) -> String {
+    let padding = spec.width.unwrap_or(0).saturating_sub(text.chars().count());
+
+    if padding == 0 {
+        return text;
     }
 
-    Ok(buffer)
+    if spec.zero_pad && numeric {
+        let (sign, digits) = text.split_at(usize::from(text.starts_with('-')));
+        return format!("{sign}{}{digits}", "0".repeat(padding));
+    }
+
+    let align = spec
+        .align
+        .unwrap_or(if numeric { Align::Right } else { Align::Left });
+    let before = match align {
+        Align::Left => 0,
+        Align::Center => padding / 2,
+        Align::Right => padding,
+    };
+    let fill = |count: usize| spec.fill.to_string().repeat(count);
+
+    format!("{}{text}{}", fill(before), fill(padding - before))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sasm_parse::ident::Identifier;
+    use std::rc::Rc;
+
+    fn storage() -> VariableStorage {
+        let mut vars = VariableStorage::default();
+
+        for (name, value) in [
+            ("n", Expression::Number(-42)),
+            ("f", Expression::Float(1.23456)),
+            ("s", Expression::make_string("{n}")),
+        ] {
+            let ident = Identifier::try_from(name).unwrap();
+            vars.create(&ident).unwrap();
+            vars.set(&ident, value).unwrap();
+        }
+
+        vars
+    }
+
+    #[test]
+    fn format_values() {
+        let vars = storage();
+        let render = |text: &str| format(&Template::parse(text).unwrap(), &vars).unwrap();
+
+        assert_eq!(render("{{{s}}}"), "{{n}}");
+        assert_eq!(
+            render("[{n:6}] [{n:<6}] [{n:*^7}] [{n:06}]"),
+            "[   -42] [-42   ] [**-42**] [-00042]"
+        );
+        assert_eq!(
+            render("{f:.2} {f:08.3} {s:>5} {s:.2}"),
+            "1.23 0001.235   {n} {n"
+        );
+        assert_eq!(render("{n:x} {s:-<4}"), "ffffffffffffffd6 {n}-");
+    }
+
+    #[test]
+    fn pad_by_chars() {
+        let mut vars = storage();
+        let u = Identifier::try_from("u").unwrap();
+        vars.create(&u).unwrap();
+        vars.set(&u, Expression::make_string("ü😀")).unwrap();
+
+        let template = Template::parse("[{u:>4}] [{u:-^5}] [{u:.1}]").unwrap();
+        assert_eq!(format(&template, &vars).unwrap(), "[  ü😀] [-ü😀--] [ü]");
+    }
+
+    #[test]
+    fn format_lists() {
+        let mut vars = storage();
+        let xs = Identifier::try_from("xs").unwrap();
+        let list = vec![Expression::make_string("a"), Expression::make_string("b")];
+        vars.create(&xs).unwrap();
+        vars.set(&xs, Expression::List(Rc::new(list))).unwrap();
+
+        let template = Template::parse("{xs} {xs:>12}").unwrap();
+        assert_eq!(
+            format(&template, &vars).unwrap(),
+            "[\"a\", \"b\"]   [\"a\", \"b\"]"
+        );
+    }
+
+    #[test]
+    fn format_maps() {
+        let mut vars = storage();
+        let m = Identifier::try_from("m").unwrap();
+        let map = [("b", 2), ("a", 1)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), Expression::Number(value)))
+            .collect();
+        vars.create(&m).unwrap();
+        vars.set(&m, Expression::Map(Rc::new(map))).unwrap();
+
+        let template = Template::parse("{m}").unwrap();
+        assert_eq!(format(&template, &vars).unwrap(), "{\"a\": 1, \"b\": 2}");
+    }
+
+    #[test]
+    fn widest_padding() {
+        let vars = storage();
+        let template = Template::parse(&format!("{{n:0{}}}", FormatSpec::MAX)).unwrap();
+
+        assert_eq!(format(&template, &vars).unwrap().len(), FormatSpec::MAX);
+        assert!(Template::parse("{n:9999999999999}").is_err());
+    }
+
+    #[test]
+    fn format_errors() {
+        let vars = storage();
+        let template = Template::parse("{s:b}").unwrap();
+
+        assert!(matches!(
+            format(&template, &vars),
+            Err(RuntimeError::MismatchedTypes { .. })
+        ));
+    }
 }
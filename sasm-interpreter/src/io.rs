@@ -0,0 +1,119 @@
+use std::{
+    collections::VecDeque,
+    io::{self, stderr, stdin, stdout, Write},
+};
+
+/// Input and output used by scripts (eg. `RNV`, `RSV`, `SAY` and `DMP`).
+pub trait IoBackend {
+    /// Reads a single line of input without the trailing newline.
+    /// Returns `None` if there's no more input.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails.
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+
+    /// Writes text to the output. No newline is added.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    fn write(&mut self, text: &str) -> io::Result<()>;
+
+    /// Writes text to the error output. No newline is added.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    fn write_err(&mut self, text: &str) -> io::Result<()>;
+}
+
+/// Reads from `stdin` and writes to `stdout` and `stderr`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdIo;
+
+/// Reads input from a list of lines and collects all output in memory.
+///
+/// ```rust
+/// # use sasm_interpreter::io::{IoBackend, MemoryIo};
+/// let mut io = MemoryIo::new(["first", "second"]);
+///
+/// assert_eq!(io.read_line().unwrap().as_deref(), Some("first"));
+/// io.write("hello").unwrap();
+///
+/// assert_eq!(io.output(), "hello");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MemoryIo {
+    input: VecDeque<String>,
+    output: String,
+    error: String,
+}
+
+impl IoBackend for StdIo {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+
+        if stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        line.truncate(line.trim_end().len());
+        Ok(Some(line))
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        let mut stdout = stdout();
+
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()
+    }
+
+    fn write_err(&mut self, text: &str) -> io::Result<()> {
+        stderr().write_all(text.as_bytes())
+    }
+}
+
+impl MemoryIo {
+    /// Creates a backend which will provide the given lines as input.
+    pub fn new<I, S>(input: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            input: input.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Adds a line to the end of the input.
+    pub fn push_input<S: Into<String>>(&mut self, line: S) {
+        self.input.push_back(line.into());
+    }
+
+    /// Returns everything written to the output so far.
+    #[must_use]
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Returns everything written to the error output so far.
+    #[must_use]
+    pub fn error_output(&self) -> &str {
+        &self.error
+    }
+}
+
+impl IoBackend for MemoryIo {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.input.pop_front())
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        self.output.push_str(text);
+        Ok(())
+    }
+
+    fn write_err(&mut self, text: &str) -> io::Result<()> {
+        self.error.push_str(text);
+        Ok(())
+    }
+}
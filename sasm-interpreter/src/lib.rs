@@ -0,0 +1,603 @@
+#![allow(
+    clippy::module_name_repetitions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+
+use comparison::Comparison;
+use core::f32;
+use error::RuntimeError;
+use executor::{execute, ExecutorState};
+use fastrand::Rng;
+use io::{IoBackend, StdIo};
+use limits::{Limit, Limits};
+use sasm_parse::{
+    expression::{Expression, Number},
+    label::JumpTarget,
+    program::Program,
+    Instruction, SpannedInstruction,
+};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use varstorage::VariableStorage;
+
+pub mod comparison;
+mod container;
+pub mod display;
+pub mod error;
+mod executor;
+mod formatter;
+pub mod io;
+pub mod limits;
+mod strings;
+pub mod varstorage;
+
+#[cfg(test)]
+mod testing;
+
+/// Executes a parsed [`Program`].
+///
+/// ```rust
+/// # use sasm_interpreter::{Interpreter, Status};
+/// # use sasm_parse::{expression::Expression, ident::Identifier, program::Program};
+/// let program = Program::parse("VAR x\nMOV x,20\nADD x,22\nDIE 3").unwrap();
+/// let mut interpreter = Interpreter::new(program);
+///
+/// assert_eq!(interpreter.run().unwrap(), 3);
+/// assert_eq!(interpreter.status(), Status::Exited(3));
+///
+/// let x = Identifier::try_from("x").unwrap();
+/// assert_eq!(interpreter.variables().get_nonnull(&x).unwrap(), &Expression::Number(42));
+/// ```
+#[derive(Debug)]
+pub struct Interpreter<I: IoBackend = StdIo> {
+    program: Program,
+    variables: VariableStorage,
+    exec_pos: usize,
+    cmp_result: Comparison,
+    status: Status,
+    io: I,
+    limits: Limits,
+    executed: u64,
+    started: Option<Instant>,
+    rng: Rng,
+    seed: u64,
+    call_stack: Vec<usize>,
+    max_call_depth: usize,
+}
+
+/// Default maximum number of nested `CALL`s.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1024;
+
+/// State of an [`Interpreter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// There are more instructions to execute.
+    Running,
+    /// The program has finished, either by reaching its end or by executing `DIE`.
+    Exited(Number),
+}
+
+impl Interpreter {
+    /// Creates an interpreter which will execute `program` from the beginning, using
+    /// `stdin` and `stdout` for I/O.
+    #[must_use]
+    pub fn new(program: Program) -> Self {
+        Self::with_io(program, StdIo)
+    }
+}
+
+impl<I: IoBackend> Interpreter<I> {
+    /// Creates an interpreter which will execute `program` from the beginning, using the given
+    /// I/O backend.
+    ///
+    /// ```rust
+    /// # use sasm_interpreter::{io::MemoryIo, Interpreter};
+    /// # use sasm_parse::program::Program;
+    /// let program = Program::parse("VAR name\nRSV name\nFMT name,\"Hi {name}!\"\nDMP name").unwrap();
+    /// let mut interpreter = Interpreter::with_io(program, MemoryIo::new(["Bob"]));
+    ///
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.io().output(), "Hi Bob!\n");
+    /// ```
+    pub fn with_io(program: Program, io: I) -> Self {
+        let mut variables = VariableStorage::new();
+        let seed = fastrand::u64(..);
+        fill_internal_vars(&mut variables, seed);
+
+        Self {
+            program,
+            variables,
+            exec_pos: 0,
+            cmp_result: Comparison::default(),
+            status: Status::Running,
+            io,
+            limits: Limits::default(),
+            executed: 0,
+            started: None,
+            rng: Rng::with_seed(seed),
+            seed,
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+
+    /// Executes the program until it exits, returning the exit code.
+    ///
+    /// # Errors
+    /// Stops at the first runtime error. The failing instruction remains available through
+    /// [`Interpreter::current`].
+    pub fn run(&mut self) -> Result<Number, RuntimeError> {
+        loop {
+            if let Status::Exited(code) = self.step()? {
+                return Ok(code);
+            }
+        }
+    }
+
+    /// Executes a single instruction of the program.
+    ///
+    /// # Errors
+    /// Returns the runtime error caused by the instruction. The position is not advanced,
+    /// so [`Interpreter::current`] returns the failing instruction.
+    pub fn step(&mut self) -> Result<Status, RuntimeError> {
+        if self.status != Status::Running {
+            return Ok(self.status);
+        }
+
+        self.check_limits()?;
+
+        let Some(instr) = self.program.get(self.exec_pos) else {
+            self.status = Status::Exited(0);
+            return Ok(self.status);
+        };
+
+        let state = execute(
+            &instr.instr,
+            &mut self.variables,
+            &mut self.cmp_result,
+            &mut self.io,
+            &mut self.rng,
+            &self.limits,
+        )?;
+        self.executed += 1;
+        self.check_string_memory()?;
+
+        match state {
+            ExecutorState::Ok => self.exec_pos += 1,
+            ExecutorState::Goto(target) => self.exec_pos = self.resolve_jump(&target)?,
+            ExecutorState::Call(target) => {
+                if self.call_stack.len() >= self.max_call_depth {
+                    return Err(RuntimeError::StackOverflow(self.max_call_depth));
+                }
+
+                let new_pos = self.resolve_jump(&target)?;
+                self.call_stack.push(self.exec_pos);
+                self.exec_pos = new_pos;
+            }
+            ExecutorState::Return => {
+                let call_site = self.call_stack.pop().ok_or(RuntimeError::EmptyCallStack)?;
+                self.exec_pos = call_site + 1;
+            }
+            ExecutorState::Sleep(duration) => {
+                self.sleep(duration)?;
+                self.exec_pos += 1;
+            }
+            ExecutorState::Exit(code) => self.status = Status::Exited(code),
+        }
+
+        if self.status == Status::Running && self.exec_pos >= self.program.len() {
+            self.status = Status::Exited(0);
+        }
+
+        Ok(self.status)
+    }
+
+    /// Executes an instruction which is not part of the program, such as one entered
+    /// into a REPL. Jumps are not supported.
+    ///
+    /// # Errors
+    /// Returns the runtime error caused by the instruction.
+    pub fn execute(&mut self, instr: &Instruction) -> Result<Status, RuntimeError> {
+        match execute(
+            instr,
+            &mut self.variables,
+            &mut self.cmp_result,
+            &mut self.io,
+            &mut self.rng,
+            &self.limits,
+        )? {
+            ExecutorState::Ok => (),
+            ExecutorState::Goto(..) | ExecutorState::Call(..) | ExecutorState::Return => {
+                return Err(RuntimeError::UnsupportedJump);
+            }
+            ExecutorState::Sleep(duration) => self.sleep(duration)?,
+            ExecutorState::Exit(code) => self.status = Status::Exited(code),
+        }
+
+        Ok(self.status)
+    }
+
+    /// Checks the instruction and time limits before executing an instruction.
+    fn check_limits(&mut self) -> Result<(), RuntimeError> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+
+        if let Some(max) = self.limits.max_instructions {
+            if self.executed >= max {
+                return Err(RuntimeError::LimitExceeded(Limit::Instructions(max)));
+            }
+        }
+
+        if let Some(max) = self.limits.max_duration {
+            if started.elapsed() > max {
+                return Err(RuntimeError::LimitExceeded(Limit::Duration(max)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_string_memory(&self) -> Result<(), RuntimeError> {
+        let Some(max) = self.limits.max_string_memory else {
+            return Ok(());
+        };
+
+        if self.variables.string_memory() > max {
+            return Err(RuntimeError::LimitExceeded(Limit::StringMemory(max)));
+        }
+
+        Ok(())
+    }
+
+    /// Sleeps for the given duration, but never past the time limit.
+    fn sleep(&self, duration: Duration) -> Result<(), RuntimeError> {
+        if let Some(max) = self.limits.max_sleep {
+            if duration > max {
+                return Err(RuntimeError::LimitExceeded(Limit::Sleep(max)));
+            }
+        }
+
+        if let (Some(max), Some(started)) = (self.limits.max_duration, self.started) {
+            let remaining = max.saturating_sub(started.elapsed());
+
+            if duration > remaining {
+                sleep(remaining);
+                return Err(RuntimeError::LimitExceeded(Limit::Duration(max)));
+            }
+        }
+
+        sleep(duration);
+        Ok(())
+    }
+
+    /// Calculates the position of the next instruction after a jump.
+    ///
+    /// Jumps may point right after the last instruction, which ends the program.
+    fn resolve_jump(&self, target: &JumpTarget) -> Result<usize, RuntimeError> {
+        self.program
+            .jump_destination(self.exec_pos, target)
+            .ok_or_else(|| match target {
+                JumpTarget::Offset(offset) => {
+                    RuntimeError::IllegalGoto(self.exec_pos as isize + *offset as isize + 1)
+                }
+                JumpTarget::Label(name) => RuntimeError::UndefinedLabel(name.clone()),
+            })
+    }
+
+    /// Returns the program being executed.
+    #[must_use]
+    pub const fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Returns the instruction which will be executed next.
+    #[must_use]
+    pub fn current(&self) -> Option<&SpannedInstruction> {
+        self.program.get(self.exec_pos)
+    }
+
+    /// Returns the index of the instruction which will be executed next.
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.exec_pos
+    }
+
+    /// Returns the result of the last comparison.
+    #[must_use]
+    pub const fn cmp_result(&self) -> Comparison {
+        self.cmp_result
+    }
+
+    /// Returns whether the program is still running or has exited.
+    #[must_use]
+    pub const fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Returns the exit code if the program has exited.
+    #[must_use]
+    pub const fn exit_code(&self) -> Option<Number> {
+        match self.status {
+            Status::Running => None,
+            Status::Exited(code) => Some(code),
+        }
+    }
+
+    /// Returns the variables of the program, including internal ones.
+    #[must_use]
+    pub const fn variables(&self) -> &VariableStorage {
+        &self.variables
+    }
+
+    /// Returns the variables of the program for modification.
+    pub fn variables_mut(&mut self) -> &mut VariableStorage {
+        &mut self.variables
+    }
+
+    /// Returns the resource limits.
+    #[must_use]
+    pub const fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Sets the resource limits. The time limit is measured from the first executed instruction.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Returns the positions of all active `CALL` instructions, starting with the outermost one.
+    #[must_use]
+    pub fn call_stack(&self) -> &[usize] {
+        &self.call_stack
+    }
+
+    /// Returns the maximum number of nested `CALL`s.
+    #[must_use]
+    pub const fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Sets the maximum number of nested `CALL`s. Exceeding it causes a [`RuntimeError::StackOverflow`].
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Returns the seed of the random number generator used by `RNG`.
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Reseeds the random number generator used by `RNG`, making the generated numbers
+    /// reproducible. The seed is also available to scripts as `_SEED`.
+    ///
+    /// ```rust
+    /// # use sasm_interpreter::Interpreter;
+    /// # use sasm_parse::{ident::Identifier, program::Program};
+    /// let program = Program::parse("VAR x\nRNG x,1,100").unwrap();
+    /// let x = Identifier::try_from("x").unwrap();
+    ///
+    /// let mut first = Interpreter::new(program.clone());
+    /// first.set_seed(42);
+    /// first.run().unwrap();
+    /// let mut second = Interpreter::new(program);
+    /// second.set_seed(42);
+    /// second.run().unwrap();
+    ///
+    /// assert_eq!(first.variables().get(&x).unwrap(), second.variables().get(&x).unwrap());
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Rng::with_seed(seed);
+        self.variables.set_internal("SEED", seed_value(seed));
+    }
+
+    /// Returns the number of instructions executed so far.
+    #[must_use]
+    pub const fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    /// Returns the I/O backend.
+    pub const fn io(&self) -> &I {
+        &self.io
+    }
+
+    /// Returns the I/O backend for modification (eg. to provide more input).
+    pub fn io_mut(&mut self) -> &mut I {
+        &mut self.io
+    }
+}
+
+fn fill_internal_vars(vars: &mut VariableStorage, seed: u64) {
+    vars.set_internal("PLATFORM", Expression::make_string("UNSPECIFIED"));
+    vars.set_internal(
+        "SASMVER",
+        Expression::make_string(env!("CARGO_PKG_VERSION")),
+    );
+    vars.set_internal("PI", Expression::Float(f32::consts::PI));
+    vars.set_internal("E", Expression::Float(f32::consts::E));
+    vars.set_internal("SEED", seed_value(seed));
+}
+
+/// Seeds are stored in `_SEED` with the same bits, so seeds above `i64::MAX` appear negative.
+const fn seed_value(seed: u64) -> Expression {
+    Expression::Number(seed as Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, Interpreter, Status};
+    use crate::{
+        error::RuntimeError,
+        io::MemoryIo,
+        limits::{Limit, Limits},
+    };
+    use sasm_parse::{expression::Expression, ident::Identifier, program::Program};
+    use std::time::{Duration, Instant};
+
+    fn interpreter(source: &str) -> Interpreter {
+        Interpreter::new(Program::parse(source).unwrap())
+    }
+
+    #[test]
+    fn step_through_loop() {
+        let mut interpreter = interpreter("VAR x\nMOV x,0\nloop:\nINC x\nCMP x,2\nJNE loop");
+        let mut steps = 0;
+
+        while interpreter.step().unwrap() == Status::Running {
+            steps += 1;
+        }
+
+        assert_eq!(steps, 7);
+        assert_eq!(interpreter.cmp_result(), Comparison::Equal);
+        assert_eq!(interpreter.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn error_keeps_position() {
+        let program = Program::parse("VAR x\nDMP x\nINC y").unwrap();
+        let mut interpreter = Interpreter::with_io(program, MemoryIo::default());
+
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::UndefinedVar(..))
+        ));
+        assert_eq!(interpreter.position(), 2);
+        assert_eq!(interpreter.status(), Status::Running);
+        assert_eq!(interpreter.io().output(), "null\n");
+    }
+
+    #[test]
+    fn scripted_io() {
+        let program = Program::parse("VAR x\nRNV x\nSAY x\nSAY '!'\nRNV x").unwrap();
+        let mut interpreter = Interpreter::with_io(program, MemoryIo::new(["12"]));
+
+        assert!(matches!(interpreter.run(), Err(RuntimeError::EndOfInput)));
+        assert_eq!(interpreter.io().output(), "12\n!");
+    }
+
+    #[test]
+    fn string_memory_limit() {
+        let mut interpreter = interpreter("VAR a\nMOV a,\"abcdef\"\nVAR b\nMOV b,a");
+        interpreter.set_limits(Limits {
+            max_string_memory: Some(10),
+            ..Limits::default()
+        });
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::LimitExceeded(Limit::StringMemory(10)))
+        ));
+        assert_eq!(interpreter.position(), 3);
+    }
+
+    #[test]
+    fn huge_repeat_hits_limit() {
+        let mut interpreter = interpreter("VAR s\nMOV s,\"ab\"\nREPEAT s,1000000000000");
+        interpreter.set_limits(Limits {
+            max_string_memory: Some(100),
+            ..Limits::default()
+        });
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::LimitExceeded(Limit::StringMemory(100)))
+        ));
+        assert_eq!(interpreter.position(), 2);
+    }
+
+    #[test]
+    fn sleep_limits() {
+        let mut capped = interpreter("HLT 60000");
+        capped.set_limits(Limits {
+            max_sleep: Some(Duration::from_secs(1)),
+            ..Limits::default()
+        });
+        assert!(matches!(
+            capped.run(),
+            Err(RuntimeError::LimitExceeded(Limit::Sleep(..)))
+        ));
+
+        let mut timed = interpreter("HLT 60000");
+        timed.set_limits(Limits {
+            max_duration: Some(Duration::from_millis(50)),
+            ..Limits::default()
+        });
+        let started = Instant::now();
+        assert!(matches!(
+            timed.run(),
+            Err(RuntimeError::LimitExceeded(Limit::Duration(..)))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn seeded_rng() {
+        let mut interpreter = interpreter("VAR x\nMOV x,_SEED\nRNG x,10,1");
+        interpreter.set_seed(7);
+
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::InvalidRange(10, 1))
+        ));
+        let x = Identifier::try_from("x").unwrap();
+        assert_eq!(
+            interpreter.variables().get_nonnull(&x).unwrap(),
+            &Expression::Number(7)
+        );
+    }
+
+    #[test]
+    fn ordering_jumps() {
+        let mut interpreter =
+            interpreter("VAR x\nMOV x,0\nloop:\nINC x\nCMP x,5\nJLT loop\nCMP x,\"5\"\nJGE loop");
+
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.cmp_result(), Comparison::Unordered);
+
+        let x = Identifier::try_from("x").unwrap();
+        assert_eq!(
+            interpreter.variables().get_nonnull(&x).unwrap(),
+            &Expression::Number(5)
+        );
+    }
+
+    #[test]
+    fn jump_to_end_halts() {
+        let program = Program::parse("DMP 1\nJMP 2\nDMP 2").unwrap();
+        let mut scripted = Interpreter::with_io(program, MemoryIo::default());
+
+        assert_eq!(scripted.run().unwrap(), 0);
+        assert_eq!(scripted.io().output(), "1\n");
+    }
+
+    #[test]
+    fn call_and_return() {
+        let mut calls =
+            interpreter("VAR x\nMOV x,0\nCALL add\nCALL add\nDIE\nadd:\nADD x,2\nCALL nested\nRET\nnested:\nINC x\nRET");
+
+        calls.run().unwrap();
+        let x = Identifier::try_from("x").unwrap();
+        assert_eq!(
+            calls.variables().get_nonnull(&x).unwrap(),
+            &Expression::Number(6)
+        );
+        assert!(calls.call_stack().is_empty());
+
+        let mut unmatched = interpreter("RET");
+        assert!(matches!(unmatched.run(), Err(RuntimeError::EmptyCallStack)));
+    }
+
+    #[test]
+    fn call_depth() {
+        let mut interpreter = interpreter("VAR x\nrecurse:\nCALL recurse");
+        interpreter.set_max_call_depth(5);
+
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::StackOverflow(5))
+        ));
+        assert_eq!(interpreter.call_stack(), &[1; 5]);
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+/// Resource limits for running untrusted scripts. All limits are disabled by default.
+///
+/// ```rust
+/// # use sasm_interpreter::{error::RuntimeError, limits::{Limit, Limits}, Interpreter};
+/// # use sasm_parse::program::Program;
+/// let program = Program::parse("loop:\nJMP loop").unwrap();
+/// let mut interpreter = Interpreter::new(program);
+///
+/// interpreter.set_limits(Limits {
+///     max_instructions: Some(1000),
+///     ..Limits::default()
+/// });
+///
+/// assert!(matches!(
+///     interpreter.run(),
+///     Err(RuntimeError::LimitExceeded(Limit::Instructions(1000)))
+/// ));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of executed instructions.
+    pub max_instructions: Option<u64>,
+    /// Maximum time the script can run for, including time spent sleeping.
+    pub max_duration: Option<Duration>,
+    /// Maximum total size of all strings stored in variables _(in bytes)_. Internal variables are not counted.
+    pub max_string_memory: Option<usize>,
+    /// Maximum duration of a single `HLT`.
+    pub max_sleep: Option<Duration>,
+}
+
+/// A limit which has been exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Instructions(u64),
+    Duration(Duration),
+    StringMemory(usize),
+    Sleep(Duration),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instructions(max) => write!(f, "executed more than {max} instructions"),
+            Self::Duration(max) => write!(f, "ran for more than {} ms", max.as_millis()),
+            Self::StringMemory(max) => write!(f, "strings use more than {max} bytes"),
+            Self::Sleep(max) => {
+                write!(f, "attempted to sleep for more than {} ms", max.as_millis())
+            }
+        }
+    }
+}
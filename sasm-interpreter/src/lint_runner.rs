@@ -0,0 +1,56 @@
+use crate::{report, report_parse_err};
+use sasm_parse::{
+    lint::{lint, Severity},
+    program::Program,
+    typecheck,
+};
+use std::fs;
+
+/// Lints a script and prints every problem found. Type errors are reported as errors.
+/// Returns the exit code: `1` if the script contains errors, `0` if it only has warnings.
+pub fn start(path: &str) -> i32 {
+    let Ok(script) = fs::read_to_string(path) else {
+        eprintln!("Failed to read script file");
+        return 1;
+    };
+
+    let program = match Program::parse_source(&script, Some(path)) {
+        Ok(program) => program,
+        Err(errors) => {
+            for error in &errors {
+                report_parse_err(&script, error);
+            }
+            return 1;
+        }
+    };
+
+    let type_errors = typecheck::check(&program).err().unwrap_or_default();
+    let mut problems: Vec<_> = type_errors
+        .into_iter()
+        .filter_map(|error| Some((Severity::Error, error.kind.to_string(), error.span?)))
+        .chain(
+            lint(&program)
+                .into_iter()
+                .map(|lint| (lint.severity(), lint.kind.to_string(), lint.span)),
+        )
+        .collect();
+    problems.sort_by_key(|(_, _, span)| (span.line, span.columns.start));
+
+    for (severity, message, span) in &problems {
+        report(&script, &severity.to_string(), message, Some(span));
+    }
+
+    let errors = problems
+        .iter()
+        .filter(|(severity, ..)| *severity == Severity::Error)
+        .count();
+    let warnings = problems.len() - errors;
+
+    if problems.is_empty() {
+        println!("No problems found");
+    } else {
+        eprintln!("{errors} error(s), {warnings} warning(s)");
+    }
+
+    i32::from(errors > 0)
+}
@@ -4,62 +4,93 @@
     clippy::cast_sign_loss
 )]
 
-use core::f32;
-use sasm_parse::{expression::Expression, Instruction};
-use std::{env, fs};
-use varstorage::VariableStorage;
+use cli::{Command, Options, USAGE};
+use sasm_parse::{error::ParseError, program::Program, span::Span, typecheck};
+use std::{env, fmt::Display, fs, process::exit};
+use tracer::Tracer;
 
-mod error;
-mod executor;
-mod formatter;
+mod cli;
+mod debugger;
+mod fmt_runner;
+mod lint_runner;
 mod repl_runner;
 mod script_runner;
-mod varstorage;
+mod tracer;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(why) => {
+            eprintln!("{why}");
+            exit(2);
+        }
+    };
+
+    if options.help {
+        println!("{USAGE}");
+        return;
+    }
 
-    if args.len() == 2 {
-        exec_script(&args[1]);
-    } else {
-        repl_runner::start();
+    match &options.script {
+        Some(path) if options.command == Command::Lint => exit(lint_runner::start(path)),
+        Some(path) if options.command == Command::Fmt => {
+            exit(fmt_runner::start(path, options.check));
+        }
+        Some(path) => exec_script(path, &options),
+        None => repl_runner::start(options.seed),
     }
 }
 
-fn exec_script(path: &str) {
+/// Runs a script, exiting with `1` if it can't be read, parsed or type-checked.
+fn exec_script(path: &str, options: &Options) {
     let Ok(script) = fs::read_to_string(path) else {
         eprintln!("Failed to read script file");
-        return;
+        exit(1);
     };
 
-    let instructions: Vec<_> = script
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(Instruction::try_from)
-        .collect();
+    let checked = Program::parse_source(&script, Some(path))
+        .and_then(|program| typecheck::check(&program).map(|()| program));
 
-    let mut errors = instructions
-        .iter()
-        .filter(|result| result.is_err())
-        .peekable();
-    if errors.peek().is_some() {
-        for (i, error) in errors.enumerate() {
-            eprintln!("Error on line {}: {}", i + 1, error.as_ref().unwrap_err());
-        }
+    match checked {
+        Ok(program) if options.debug => debugger::start(program, options),
+        Ok(program) => {
+            let tracer = match (&options.trace_file, options.trace) {
+                (Some(path), _) => match Tracer::json_file(path) {
+                    Ok(tracer) => Some(tracer),
+                    Err(why) => {
+                        eprintln!("Failed to create trace file: {why}");
+                        exit(1);
+                    }
+                },
+                (None, true) => Some(Tracer::stderr()),
+                (None, false) => None,
+            };
 
-        return;
+            script_runner::start(program, options, tracer);
+        }
+        Err(errors) => {
+            for error in &errors {
+                report_parse_err(&script, error);
+            }
+            exit(1);
+        }
     }
+}
 
-    let commands: Vec<_> = instructions.into_iter().map(Result::unwrap).collect();
-    script_runner::start(&commands);
+fn report_parse_err(script: &str, error: &ParseError) {
+    report(script, "Error", error, error.span.as_ref());
 }
 
-pub fn fill_internal_vars(vars: &mut VariableStorage) {
-    vars.set_internal("PLATFORM", Expression::make_string("UNSPECIFIED"));
-    vars.set_internal(
-        "SASMVER",
-        Expression::make_string(env!("CARGO_PKG_VERSION")),
-    );
-    vars.set_internal("PI", Expression::Float(f32::consts::PI));
-    vars.set_internal("E", Expression::Float(f32::consts::E));
+/// Prints a message about the script, pointing at the given location.
+fn report(script: &str, severity: &str, message: &dyn Display, span: Option<&Span>) {
+    let Some(span) = span else {
+        eprintln!("{severity}: {message}");
+        return;
+    };
+
+    let source_line = script.lines().nth(span.line - 1).unwrap_or_default();
+
+    eprintln!("{severity} on {span}: {message}");
+    eprintln!("\t{source_line}");
+    eprintln!("\t{}", span.underline(source_line));
 }
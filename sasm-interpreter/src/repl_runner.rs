@@ -1,46 +1,47 @@
-use crate::{executor::execute, fill_internal_vars, varstorage::VariableStorage};
-use sasm_parse::Instruction;
-use std::io::{stdin, stdout, Write};
-
-pub fn start() {
-    let mut variables = VariableStorage::new();
-    let mut line = String::new();
-    let mut cmp_result = false;
-
-    fill_internal_vars(&mut variables);
+use sasm_interpreter::{io::IoBackend, Interpreter, Status};
+use sasm_parse::{program::Program, Instruction};
+use std::{
+    io::{stdout, Write},
+    process::exit,
+};
+
+pub fn start(seed: Option<u64>) {
+    let mut interpreter = Interpreter::new(Program::default());
+
+    if let Some(seed) = seed {
+        interpreter.set_seed(seed);
+    }
 
     println!("SASM Interpreter");
     println!("v{}\n", env!("CARGO_PKG_VERSION"));
 
     display_prompt();
 
-    while stdin().read_line(&mut line).is_ok() {
-        line = line.trim_end().to_string();
+    while let Ok(Some(line)) = interpreter.io_mut().read_line() {
+        let parsed = Instruction::parse_spanned(&line, None, 1);
 
-        if line.is_empty() {
-            display_prompt();
-            continue;
-        }
-
-        match Instruction::try_from(line.as_str()) {
-            Ok(Instruction::JumpNotEqual(..))
-            | Ok(Instruction::JumpEqual(..))
-            | Ok(Instruction::Jump(..)) => {
+        match parsed.map(|maybe| maybe.map(|spanned| spanned.instr)) {
+            Ok(None) => (),
+            Ok(Some(instr)) if instr.jump_target().is_some() => {
                 eprintln!("Jumps are not supported in REPL mode");
             }
-            Ok(instr) => match execute(&instr, &mut variables, &mut cmp_result) {
-                Ok(_) => (),
+            Ok(Some(instr)) => match interpreter.execute(&instr) {
+                Ok(Status::Running) => (),
+                Ok(Status::Exited(code)) => exit(code as i32),
                 Err(why) => {
                     eprintln!("Runtime error: {why}");
                 }
             },
             Err(why) => {
+                if let Some(span) = &why.span {
+                    // Align the carets with the input line, right after the prompt
+                    eprintln!("    {}", span.underline(&line));
+                }
                 eprintln!("Failed to parse input: {why}");
             }
         }
 
         display_prompt();
-        line.clear();
     }
 }
 
@@ -1,45 +1,133 @@
-use crate::{
-    error::RuntimeError,
-    executor::{execute, ExecutorState},
-    fill_internal_vars,
-    varstorage::VariableStorage,
-};
-use sasm_parse::Instruction;
-
-pub fn start(cmds: &[Instruction]) {
-    let mut variables = VariableStorage::new();
-    let mut exec_pos = 0;
-    let mut cmp_result = false;
-
-    fill_internal_vars(&mut variables);
-
-    while exec_pos < cmds.len() {
-        let instr = &cmds[exec_pos];
-
-        match execute(instr, &mut variables, &mut cmp_result) {
-            Ok(ExecutorState::Ok) => exec_pos += 1,
-            Ok(ExecutorState::Goto(offset)) => {
-                if offset > 0 {
-                    exec_pos += offset as usize;
-                } else {
-                    exec_pos -= offset.unsigned_abs();
-                }
+use crate::{cli::Options, tracer::Tracer};
+use sasm_interpreter::{error::RuntimeError, io::IoBackend, Interpreter, Status};
+use sasm_parse::{expression::Number, program::Program, span::Span, SpannedInstruction};
+use std::{fmt::Write as _, process::exit};
+
+/// Exit code used when a script exceeds one of its resource limits.
+pub const LIMIT_EXIT_CODE: i32 = 124;
 
-                if cmds.get(exec_pos).is_none() {
-                    report_runtime_err(exec_pos, instr, &RuntimeError::IllegalGoto(exec_pos + 1));
-                    break;
+pub fn start(program: Program, options: &Options, mut tracer: Option<Tracer>) {
+    let mut interpreter = Interpreter::new(program);
+    interpreter.set_limits(options.limits);
+    options.configure(&mut interpreter);
+    let result = match tracer.as_mut() {
+        Some(tracer) => run_traced(&mut interpreter, tracer),
+        None => interpreter.run(),
+    };
+
+    if let Some(Err(why)) = tracer.as_mut().map(Tracer::finish) {
+        eprintln!("Failed to write trace: {why}");
+    }
+
+    match result {
+        Ok(code) => exit(code as i32),
+        Err(why) => {
+            let report = match interpreter.current() {
+                Some(instr) => {
+                    format_runtime_err(interpreter.program(), instr, &why, interpreter.call_stack())
                 }
+                None => format!("Runtime error: {why}\n"),
+            };
+
+            // Nothing else can be done if the report itself can't be written
+            let _ = interpreter.io_mut().write_err(&report);
+
+            if matches!(why, RuntimeError::LimitExceeded(..)) {
+                exit(LIMIT_EXIT_CODE);
             }
-            Err(why) => {
-                report_runtime_err(exec_pos, instr, &why);
-                break;
-            }
         }
     }
 }
 
-fn report_runtime_err(exec_pos: usize, instr: &Instruction, err: &RuntimeError) {
-    eprintln!("Runtime error on line {}:", exec_pos + 1);
-    eprintln!("\t-> {instr}");
-    eprintln!("\t|- {err}");
+/// Runs a program like [`Interpreter::run()`], recording every executed instruction.
+pub fn run_traced<I: IoBackend>(
+    interpreter: &mut Interpreter<I>,
+    tracer: &mut Tracer,
+) -> Result<Number, RuntimeError> {
+    loop {
+        let Some(instr) = interpreter.current().cloned() else {
+            return interpreter.run();
+        };
+        let cmp_result = interpreter.cmp_result();
+        let before = interpreter.variables().clone();
+
+        let status = interpreter.step()?;
+        tracer.record(
+            interpreter.program(),
+            &instr,
+            cmp_result,
+            &before,
+            interpreter.variables(),
+        )?;
+
+        if let Status::Exited(code) = status {
+            return Ok(code);
+        }
+    }
+}
+
+/// Finds the argument which caused the error, or falls back to the whole instruction.
+fn offending_span(program: &Program, instr: &SpannedInstruction, err: &RuntimeError) -> Span {
+    if matches!(
+        err,
+        RuntimeError::IllegalGoto(..) | RuntimeError::UndefinedLabel(..)
+    ) {
+        return instr.span.arg_or_full(0);
+    }
+
+    let full = instr.span.full();
+    let Some(ident) = err.identifier() else {
+        return full;
+    };
+
+    let source_line = program.source_line(full.line).unwrap_or_default();
+    instr
+        .span
+        .args
+        .iter()
+        .find(|span| span.text(source_line) == ident.name())
+        .cloned()
+        .unwrap_or(full)
+}
+
+/// Formats a runtime error report. `call_stack` contains the positions of the active `CALL`
+/// instructions, which are listed after the error starting with the innermost one.
+pub fn format_runtime_err(
+    program: &Program,
+    instr: &SpannedInstruction,
+    err: &RuntimeError,
+    call_stack: &[usize],
+) -> String {
+    let span = offending_span(program, instr, err);
+    let source_line = program.source_line(span.line).unwrap_or_default();
+
+    let mut report = format!(
+        "Runtime error on {span}:\n\t-> {source_line}\n\t   {}\n\t|- {}: {err}\n",
+        span.underline(source_line),
+        instr.instr
+    );
+
+    // Recursive calls are collapsed into a single line
+    let mut frames = call_stack.iter().rev().peekable();
+
+    while let Some(index) = frames.next() {
+        let mut repeated = 1;
+        while frames.next_if_eq(&index).is_some() {
+            repeated += 1;
+        }
+
+        let Some(call) = program.get(*index) else {
+            continue;
+        };
+        let call_span = call.span.full();
+        let call_line = program.source_line(call_span.line).unwrap_or_default();
+
+        let _ = write!(report, "\tcalled from {call_span}: {}", call_line.trim());
+        let _ = match repeated {
+            1 => writeln!(report),
+            n => writeln!(report, " ({n} times)"),
+        };
+    }
+
+    report
 }
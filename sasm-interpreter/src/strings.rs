@@ -0,0 +1,274 @@
+use crate::{container::position, display::display, error::RuntimeError, limits::Limit};
+use sasm_parse::{
+    expression::{Expression, Number},
+    type_trait::SasmType,
+};
+use std::rc::Rc;
+
+/// Returns the characters between `start` _(inclusive)_ and `end` _(exclusive)_.
+/// Without `end`, all characters after `start` are returned.
+///
+/// All indices in this module are counted in characters _(Unicode scalar values)_, not bytes.
+pub fn substring(
+    text: &str,
+    start: &Expression,
+    end: Option<&Expression>,
+) -> Result<String, RuntimeError> {
+    let len = text.chars().count();
+    let first = position(start, len, true)?;
+    let last = end.map_or(Ok(len), |end| position(end, len, true))?;
+
+    if last < first {
+        return Err(RuntimeError::InvalidRange(
+            first.try_into()?,
+            last.try_into()?,
+        ));
+    }
+
+    Ok(text.chars().skip(first).take(last - first).collect())
+}
+
+/// Returns the character at an index as a single character string.
+pub fn char_at(text: &str, index: &Expression) -> Result<Expression, RuntimeError> {
+    let pos = position(index, text.chars().count(), false)?;
+    let ch = text.chars().nth(pos).expect("position is in bounds");
+
+    Ok(Expression::singe_char_string(ch))
+}
+
+/// Returns the index of the first occurrence of `needle`, or `-1`.
+pub fn find(text: &str, needle: &str) -> Result<Number, RuntimeError> {
+    match text.find(needle) {
+        Some(byte_pos) => Ok(text[..byte_pos].chars().count().try_into()?),
+        None => Ok(-1),
+    }
+}
+
+/// Returns the string repeated `count` times.
+///
+/// The size of the result is checked before it's allocated: it must not be larger than
+/// `max_bytes` _(the string memory limit, if set)_ or than the memory available.
+pub fn repeat(text: &str, count: Number, max_bytes: Option<usize>) -> Result<String, RuntimeError> {
+    let (count, bytes) = usize::try_from(count)
+        .ok()
+        .and_then(|n| Some((n, text.len().checked_mul(n)?)))
+        .ok_or(RuntimeError::IllegalCount(count))?;
+
+    if let Some(max) = max_bytes.filter(|max| bytes > *max) {
+        return Err(RuntimeError::LimitExceeded(Limit::StringMemory(max)));
+    }
+
+    let mut repeated = String::new();
+    repeated
+        .try_reserve_exact(bytes)
+        .map_err(|_| RuntimeError::OutOfMemory(bytes))?;
+
+    if !text.is_empty() {
+        repeated.extend(std::iter::repeat_n(text, count));
+    }
+
+    Ok(repeated)
+}
+
+/// Splits a string into a list of strings. An empty separator splits it into characters.
+pub fn split(text: &str, separator: &str) -> Expression {
+    let parts = if separator.is_empty() {
+        text.chars().map(Expression::singe_char_string).collect()
+    } else {
+        text.split(separator).map(Expression::make_string).collect()
+    };
+
+    Expression::List(Rc::new(parts))
+}
+
+/// Returns the code point of a single character string.
+pub fn char_code(value: &Expression) -> Result<Number, RuntimeError> {
+    if let Expression::String(text) = value {
+        let mut chars = text.chars();
+
+        if let (Some(ch), None) = (chars.next(), chars.next()) {
+            return Ok(u32::from(ch).into());
+        }
+    }
+
+    Err(RuntimeError::InvalidConversion {
+        value: display(value),
+        from: value.type_name(),
+        target: "Character",
+    })
+}
+
+/// Returns the character with the given code point as a single character string.
+pub fn char_from_code(code: Number) -> Result<Expression, RuntimeError> {
+    u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .map(Expression::singe_char_string)
+        .ok_or_else(|| RuntimeError::InvalidConversion {
+            value: code.to_string(),
+            from: Number::type_name(),
+            target: "Character",
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{error, output};
+
+    #[test]
+    fn unicode_indices() {
+        let text = "héllo wörld";
+        let n = Expression::Number;
+
+        assert_eq!(substring(text, &n(1), Some(&n(4))).unwrap(), "éll");
+        assert_eq!(substring(text, &n(7), None).unwrap(), "örld");
+        assert_eq!(substring(text, &n(11), None).unwrap(), "");
+        assert_eq!(find(text, "wö").unwrap(), 6);
+        assert_eq!(find(text, "x").unwrap(), -1);
+        assert_eq!(char_at(text, &n(7)).unwrap(), Expression::make_string("ö"));
+        assert_eq!(char_code(&Expression::make_string("ö")).unwrap(), 246);
+        assert_eq!(
+            char_from_code(0x1F600).unwrap(),
+            Expression::make_string("😀")
+        );
+    }
+
+    #[test]
+    fn split_strings() {
+        let list = |parts: &[&str]| {
+            Expression::List(Rc::new(parts.iter().map(Expression::make_string).collect()))
+        };
+
+        assert_eq!(split("a, b,c", ", "), list(&["a", "b,c"]));
+        assert_eq!(split("äb", ""), list(&["ä", "b"]));
+        assert_eq!(split("", ","), list(&[""]));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let n = Expression::Number;
+
+        assert!(matches!(
+            substring("abc", &n(2), Some(&n(1))),
+            Err(RuntimeError::InvalidRange(2, 1))
+        ));
+        assert!(matches!(
+            substring("äbc", &n(4), None),
+            Err(RuntimeError::IndexOutOfBounds { index: 4, len: 3 })
+        ));
+        assert!(matches!(
+            repeat("ab", -1, None),
+            Err(RuntimeError::IllegalCount(-1))
+        ));
+        assert!(char_code(&Expression::make_string("ab")).is_err());
+        assert!(char_from_code(0xD800).is_err());
+    }
+
+    #[test]
+    fn repeat_checks_size_first() {
+        assert_eq!(repeat("ab", 3, Some(6)).unwrap(), "ababab");
+        assert_eq!(repeat("", Number::MAX, Some(0)).unwrap(), "");
+        assert!(matches!(
+            repeat("ab", 4, Some(6)),
+            Err(RuntimeError::LimitExceeded(Limit::StringMemory(6)))
+        ));
+        assert!(matches!(
+            repeat("ab", Number::MAX, None),
+            Err(RuntimeError::OutOfMemory(..))
+        ));
+    }
+
+    #[test]
+    fn trim_and_change_case() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"  Grüße, Welt \"\nTRIM s\nUPPER s\nDMP s\nLOWER s\nDMP s"),
+            "GRÜSSE, WELT\ngrüsse, welt\n"
+        );
+    }
+
+    #[test]
+    fn reverse_and_repeat() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"äb\"\nREVERSE s\nREPEAT s,2\nDMP s"),
+            "bäbä\n"
+        );
+    }
+
+    #[test]
+    fn replace_text() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"a-b-c\"\nREPLACE s,\"-\",\", \"\nDMP s"),
+            "a, b, c\n"
+        );
+    }
+
+    #[test]
+    fn find_and_substring() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"Grüße, Welt\"\nVAR i\nFIND i,s,\"ße\"\nDMP i\nVAR t\nSUBSTR t,s,0,i\nDMP t"),
+            "3\nGrü\n"
+        );
+    }
+
+    #[test]
+    fn char_codes() {
+        assert_eq!(
+            output("VAR c\nMOV c,\"ü\"\nVAR i\nORD i,c\nDMP i\nINC i\nCHR c,i\nDMP c"),
+            "252\ný\n"
+        );
+    }
+
+    #[test]
+    fn split_string() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"a, b\"\nVAR parts\nSPLIT parts,s,\", \"\nDMP parts"),
+            "[\"a\", \"b\"]\n"
+        );
+    }
+
+    #[test]
+    fn negative_repeat() {
+        assert!(matches!(
+            error("VAR s\nMOV s,\"a\"\nREPEAT s,-1"),
+            RuntimeError::IllegalCount(-1)
+        ));
+    }
+
+    #[test]
+    fn length_counts_chars() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"naïve 😀\"\nVAR n\nLEN n,s\nDMP n"),
+            "7\n"
+        );
+    }
+
+    #[test]
+    fn pop_removes_last_char() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"naïve😀\"\nVAR c\nPOP s,c\nDMP c\nDMP s"),
+            "😀\nnaïve\n"
+        );
+    }
+
+    #[test]
+    fn index_chars() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"naïve\"\nVAR c\nGET c,s,2\nDMP c"),
+            "ï\n"
+        );
+
+        assert!(matches!(
+            error("VAR s\nMOV s,\"ï😀\"\nVAR c\nGET c,s,2"),
+            RuntimeError::IndexOutOfBounds { index: 2, len: 2 }
+        ));
+    }
+
+    #[test]
+    fn substring_from_length_is_empty() {
+        assert_eq!(
+            output("VAR s\nMOV s,\"ï😀\"\nVAR n\nLEN n,s\nVAR t\nSUBSTR t,s,n\nLEN n,t\nDMP n"),
+            "0\n"
+        );
+    }
+}
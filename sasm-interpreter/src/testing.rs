@@ -0,0 +1,22 @@
+//! Helpers shared by the tests of the interpreter modules.
+
+use crate::{error::RuntimeError, io::MemoryIo, Interpreter};
+use sasm_parse::program::Program;
+
+/// Creates an interpreter for a script, with its output kept in memory.
+pub fn scripted(source: &str) -> Interpreter<MemoryIo> {
+    Interpreter::with_io(Program::parse(source).unwrap(), MemoryIo::default())
+}
+
+/// Runs a script which must succeed and returns its output.
+pub fn output(source: &str) -> String {
+    let mut interpreter = scripted(source);
+    interpreter.run().unwrap();
+
+    interpreter.io().output().to_string()
+}
+
+/// Runs a script which must fail and returns the error.
+pub fn error(source: &str) -> RuntimeError {
+    scripted(source).run().unwrap_err()
+}
@@ -0,0 +1,311 @@
+use sasm_interpreter::{comparison::Comparison, display::quoted, varstorage::VariableStorage};
+use sasm_parse::{expression::Expression, ident::Identifier, program::Program, SpannedInstruction};
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, stderr, BufWriter, Write},
+};
+
+/// Records every executed instruction.
+pub struct Tracer {
+    output: Box<dyn Write>,
+    json: bool,
+    steps: usize,
+}
+
+/// A change of a single variable caused by an instruction.
+enum Change<'a> {
+    Set(&'a Identifier, Option<&'a Expression>),
+    Deleted(&'a Identifier),
+}
+
+impl Tracer {
+    /// Creates a tracer which prints human-readable text to `stderr`.
+    pub fn stderr() -> Self {
+        Self {
+            output: Box::new(stderr()),
+            json: false,
+            steps: 0,
+        }
+    }
+
+    /// Creates a tracer which writes JSON lines into a file.
+    pub fn json_file(path: &str) -> io::Result<Self> {
+        Ok(Self {
+            output: Box::new(BufWriter::new(File::create(path)?)),
+            json: true,
+            steps: 0,
+        })
+    }
+
+    /// Records the execution of `instr`.
+    ///
+    /// `cmp_result` and `before` are the state of the interpreter before the instruction was executed.
+    pub fn record(
+        &mut self,
+        program: &Program,
+        instr: &SpannedInstruction,
+        cmp_result: Comparison,
+        before: &VariableStorage,
+        after: &VariableStorage,
+    ) -> io::Result<()> {
+        self.steps += 1;
+
+        let line = program.source_line(instr.span.line()).unwrap_or_default();
+        let jump = cmp_result.jump_taken(&instr.instr);
+        let changes = diff(before, after);
+
+        let entry = if self.json {
+            self.json_entry(instr, line, jump, &changes)
+        } else {
+            self.text_entry(instr, line, jump, &changes)
+        };
+
+        self.output.write_all(entry.as_bytes())
+    }
+
+    /// Writes any buffered entries.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn text_entry(
+        &self,
+        instr: &SpannedInstruction,
+        line: &str,
+        jump: Option<bool>,
+        changes: &[Change],
+    ) -> String {
+        let mut entry = format!(
+            "[trace] #{} line {}: {}",
+            self.steps,
+            instr.span.line(),
+            instr.span.full().text(line)
+        );
+
+        match jump {
+            Some(true) => entry.push_str(" (jump taken)"),
+            Some(false) => entry.push_str(" (jump not taken)"),
+            None => (),
+        }
+        entry.push('\n');
+
+        for change in changes {
+            let _ = match change {
+                Change::Set(ident, None) => writeln!(entry, "[trace]     {ident} = null"),
+                Change::Set(ident, Some(value)) => {
+                    writeln!(entry, "[trace]     {ident} = {}", quoted(value))
+                }
+                Change::Deleted(ident) => writeln!(entry, "[trace]     {ident} deleted"),
+            };
+        }
+
+        entry
+    }
+
+    fn json_entry(
+        &self,
+        instr: &SpannedInstruction,
+        line: &str,
+        jump: Option<bool>,
+        changes: &[Change],
+    ) -> String {
+        let args: Vec<_> = instr
+            .span
+            .args
+            .iter()
+            .map(|span| json_string(span.text(line)))
+            .collect();
+        let jump = jump.map_or("null", |taken| if taken { "true" } else { "false" });
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|change| match change {
+                Change::Set(ident, value) => format!(
+                    "{{\"var\":{},\"value\":{}}}",
+                    json_string(ident.name()),
+                    json_value(*value)
+                ),
+                Change::Deleted(ident) => {
+                    format!("{{\"var\":{},\"deleted\":true}}", json_string(ident.name()))
+                }
+            })
+            .collect();
+
+        format!(
+            "{{\"step\":{},\"line\":{},\"source\":{},\"instruction\":{},\"args\":[{}],\"jump_taken\":{},\"changes\":[{}]}}\n",
+            self.steps,
+            instr.span.line(),
+            json_string(line),
+            json_string(instr.span.mnemonic.text(line)),
+            args.join(","),
+            jump,
+            changes.join(",")
+        )
+    }
+}
+
+/// Finds all variables which were created, modified or deleted, sorted by name.
+fn diff<'a>(before: &'a VariableStorage, after: &'a VariableStorage) -> Vec<Change<'a>> {
+    let mut changes: Vec<_> = after
+        .iter()
+        .filter(|(ident, value)| before.get(ident).ok() != Some(*value))
+        .map(|(ident, value)| Change::Set(ident, value))
+        .chain(
+            before
+                .iter()
+                .filter(|(ident, _)| after.get(ident).is_err())
+                .map(|(ident, _)| Change::Deleted(ident)),
+        )
+        .collect();
+
+    changes.sort_by_key(|change| match change {
+        Change::Set(ident, _) | Change::Deleted(ident) => *ident,
+    });
+    changes
+}
+
+fn json_string(text: &str) -> String {
+    let mut output = String::with_capacity(text.len() + 2);
+    output.push('"');
+
+    for ch in text.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(output, "\\u{:04x}", ch as u32);
+            }
+            ch => output.push(ch),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+fn json_value(value: Option<&Expression>) -> String {
+    match value {
+        None => "null".into(),
+        Some(Expression::Number(n)) => n.to_string(),
+        Some(Expression::Float(v)) if v.is_finite() => v.to_string(),
+        Some(Expression::Float(v)) => json_string(&v.to_string()),
+        Some(Expression::String(text)) => json_string(text),
+        Some(Expression::List(list)) => {
+            let elements: Vec<_> = list.iter().map(|value| json_value(Some(value))).collect();
+            format!("[{}]", elements.join(","))
+        }
+        Some(Expression::Map(map)) => {
+            let entries: Vec<_> = map
+                .iter()
+                .map(|(key, value)| format!("{}:{}", json_string(key), json_value(Some(value))))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        Some(Expression::Identifier(..)) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_string, Tracer};
+    use crate::script_runner::run_traced;
+    use sasm_interpreter::{io::MemoryIo, Interpreter};
+    use sasm_parse::program::Program;
+    use std::{
+        cell::RefCell,
+        io::{self, Write},
+        rc::Rc,
+    };
+
+    /// Collects the trace so it can be read after the tracer took ownership of it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn trace(source: &str, json: bool) -> Vec<String> {
+        let buffer = SharedBuffer::default();
+        let mut tracer = Tracer {
+            output: Box::new(buffer.clone()),
+            json,
+            steps: 0,
+        };
+        let program = Program::parse(source).unwrap();
+        let mut interpreter = Interpreter::with_io(program, MemoryIo::default());
+
+        run_traced(&mut interpreter, &mut tracer).unwrap();
+        tracer.finish().unwrap();
+
+        let output = String::from_utf8(buffer.0.take()).unwrap();
+        output.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn json_lines() {
+        let lines = trace(
+            "VAR x\nMOV x,1\nCMP x,2\nJEQ end\nJNE end\nDMP x\nend:\nDEL x",
+            true,
+        );
+
+        assert_eq!(
+            lines,
+            [
+                r#"{"step":1,"line":1,"source":"VAR x","instruction":"VAR","args":["x"],"jump_taken":null,"changes":[{"var":"x","value":null}]}"#,
+                r#"{"step":2,"line":2,"source":"MOV x,1","instruction":"MOV","args":["x","1"],"jump_taken":null,"changes":[{"var":"x","value":1}]}"#,
+                r#"{"step":3,"line":3,"source":"CMP x,2","instruction":"CMP","args":["x","2"],"jump_taken":null,"changes":[]}"#,
+                r#"{"step":4,"line":4,"source":"JEQ end","instruction":"JEQ","args":["end"],"jump_taken":false,"changes":[]}"#,
+                r#"{"step":5,"line":5,"source":"JNE end","instruction":"JNE","args":["end"],"jump_taken":true,"changes":[]}"#,
+                r#"{"step":6,"line":8,"source":"DEL x","instruction":"DEL","args":["x"],"jump_taken":null,"changes":[{"var":"x","deleted":true}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn json_values() {
+        let lines = trace(
+            "VAR xs\nLST xs\nPSH xs,\"a\\\"b\"\nVAR m\nMAP m\nSET m,\"k\",1.5",
+            true,
+        );
+
+        assert!(lines[2].ends_with(r#""changes":[{"var":"xs","value":["a\"b"]}]}"#));
+        assert!(lines[5].ends_with(r#""changes":[{"var":"m","value":{"k":1.5}}]}"#));
+    }
+
+    #[test]
+    fn text_lines() {
+        let lines = trace("VAR x\nMOV x,1\nJMP 2\nDIE\nDEL x", false);
+
+        assert_eq!(
+            lines,
+            [
+                "[trace] #1 line 1: VAR x",
+                "[trace]     x = null",
+                "[trace] #2 line 2: MOV x,1",
+                "[trace]     x = 1",
+                "[trace] #3 line 3: JMP 2 (jump taken)",
+                "[trace] #4 line 5: DEL x",
+                "[trace]     x deleted",
+            ]
+        );
+    }
+
+    #[test]
+    fn escape_json_strings() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(
+            json_string("say \"hi\"\\\n\t\u{1}"),
+            "\"say \\\"hi\\\"\\\\\\n\\t\\u0001\""
+        );
+    }
+}
@@ -1,13 +1,18 @@
-use crate::error::RuntimeError;
+use crate::{container::check_element, error::RuntimeError};
 use sasm_parse::{expression::Expression, ident::Identifier};
 use std::collections::HashMap;
 
-#[derive(Debug, Default)]
-pub struct VariableStorage(HashMap<Identifier, Option<Expression>>);
+#[derive(Debug, Default, Clone)]
+pub struct VariableStorage {
+    values: HashMap<Identifier, Option<Expression>>,
+    /// The first element stored in each list or map variable. It keeps fixing the type of the
+    /// elements after the container is emptied.
+    elements: HashMap<Identifier, Expression>,
+}
 
 impl VariableStorage {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self::default()
     }
 
     pub fn create(&mut self, ident: &Identifier) -> Result<(), RuntimeError> {
@@ -15,12 +20,12 @@ impl VariableStorage {
             return Err(RuntimeError::DuplicateVarDef(ident.clone()));
         }
 
-        self.0.insert(ident.clone(), None);
+        self.values.insert(ident.clone(), None);
         Ok(())
     }
 
     pub fn get(&self, ident: &Identifier) -> Result<Option<&Expression>, RuntimeError> {
-        let Some(maybe) = self.0.get(ident) else {
+        let Some(maybe) = self.values.get(ident) else {
             return Err(RuntimeError::UndefinedVar(ident.clone()));
         };
 
@@ -39,6 +44,8 @@ impl VariableStorage {
         Ok(value)
     }
 
+    /// Assigns a value to a variable, creating it if needed. The type of a variable is fixed by
+    /// its first value, and the type of the elements of a list or map by its first element.
     pub fn set(&mut self, ident: &Identifier, value: Expression) -> Result<(), RuntimeError> {
         if ident.is_internal() {
             return Err(RuntimeError::IllegalWriteInternal(ident.clone()));
@@ -54,11 +61,29 @@ impl VariableStorage {
 
     pub fn delete(&mut self, ident: &Identifier) -> Result<(), RuntimeError> {
         let _ = self.get(ident)?;
-        self.0.remove(ident);
+        self.values.remove(ident);
+        self.elements.remove(ident);
 
         Ok(())
     }
 
+    /// Returns all defined variables, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, Option<&Expression>)> {
+        self.values
+            .iter()
+            .map(|(ident, value)| (ident, value.as_ref()))
+    }
+
+    /// Returns the total size of all strings stored in non-internal variables _(in bytes)_,
+    /// including strings inside lists and maps.
+    pub fn string_memory(&self) -> usize {
+        self.iter()
+            .filter(|(ident, _)| !ident.is_internal())
+            .filter_map(|(_, value)| value)
+            .map(string_bytes)
+            .sum()
+    }
+
     pub fn set_internal(&mut self, name: &'static str, value: Expression) {
         let ident = Identifier::try_from(format!("_{name}").as_str()).unwrap();
 
@@ -80,11 +105,87 @@ impl VariableStorage {
             });
         }
 
-        self.0.insert(ident.clone(), Some(value));
+        // Lists and maps also keep the type of their elements
+        if let Some(element) = first_element(&value) {
+            check_element(self.elements.get(ident), element)?;
+        }
+
+        self.insert(ident, value);
         Ok(())
     }
 
     fn insert(&mut self, ident: &Identifier, value: Expression) {
-        self.0.insert(ident.clone(), Some(value));
+        if let Some(element) = first_element(&value) {
+            self.elements
+                .entry(ident.clone())
+                .or_insert_with(|| element.clone());
+        }
+
+        self.values.insert(ident.clone(), Some(value));
+    }
+}
+
+fn first_element(value: &Expression) -> Option<&Expression> {
+    match value {
+        Expression::List(list) => list.first(),
+        Expression::Map(map) => map.values().next(),
+        _ => None,
+    }
+}
+
+fn string_bytes(value: &Expression) -> usize {
+    match value {
+        Expression::String(text) => text.len(),
+        Expression::List(list) => list.iter().map(string_bytes).sum(),
+        Expression::Map(map) => map
+            .iter()
+            .map(|(key, value)| key.len() + string_bytes(value))
+            .sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VariableStorage;
+    use crate::error::RuntimeError;
+    use sasm_parse::{expression::Expression, ident::Identifier};
+    use std::{collections::BTreeMap, rc::Rc};
+
+    #[test]
+    fn emptied_list_keeps_element_type() {
+        let list = |elements: Vec<Expression>| Expression::List(Rc::new(elements));
+        let xs = Identifier::try_from("xs").unwrap();
+        let mut vars = VariableStorage::new();
+
+        vars.set(&xs, list(vec![Expression::Number(1)])).unwrap();
+        vars.set(&xs, Expression::empty_list()).unwrap();
+        assert!(matches!(
+            vars.set(&xs, list(vec![Expression::make_string("a")])),
+            Err(RuntimeError::MismatchedTypes {
+                got: "String",
+                expected: "Number"
+            })
+        ));
+        vars.set(&xs, list(vec![Expression::Number(2)])).unwrap();
+
+        // Deleting the variable forgets its type
+        vars.delete(&xs).unwrap();
+        vars.set(&xs, list(vec![Expression::make_string("a")]))
+            .unwrap();
+    }
+
+    #[test]
+    fn emptied_map_keeps_element_type() {
+        let map = |value: Expression| {
+            Expression::Map(Rc::new(BTreeMap::from([("k".to_string(), value)])))
+        };
+        let m = Identifier::try_from("m").unwrap();
+        let mut vars = VariableStorage::new();
+
+        vars.set(&m, Expression::empty_map()).unwrap();
+        vars.set(&m, map(Expression::Float(1.0))).unwrap();
+        vars.set(&m, Expression::empty_map()).unwrap();
+        assert!(vars.set(&m, map(Expression::Number(1))).is_err());
     }
 }
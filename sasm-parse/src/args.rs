@@ -1,34 +1,53 @@
 use crate::{
-    error::ParseError,
-    expression::{Expression, Number},
+    error::{ParseError, ParseErrorKind},
+    expression::{Expression, Number, Text},
     ident::Identifier,
+    label::JumpTarget,
+    span::Span,
+    template::Template,
     type_trait::SasmType,
 };
 
 pub enum ArgFetchResult<T> {
     Found(T),
-    Missing,
-    InvalidType { got: String, expected: String },
+    Missing(Span),
+    InvalidType {
+        got: String,
+        expected: String,
+        span: Span,
+    },
 }
 
-pub struct Arguments(Vec<Expression>);
+pub struct Arguments {
+    args: Vec<(Expression, Span)>,
+    /// Location right after the last argument, used for reporting missing arguments.
+    end: Span,
+}
 
 impl Arguments {
+    pub fn new(args: Vec<(Expression, Span)>, end: Span) -> Self {
+        Self { args, end }
+    }
+
     pub fn check_count(&self, min: usize, max: usize) -> Result<(), ParseError> {
-        let argc = self.0.len();
+        let argc = self.args.len();
 
         if argc < min {
-            return Err(ParseError::NotEnoughArgs {
+            return Err(ParseErrorKind::NotEnoughArgs {
                 got: argc,
                 expected: min,
-            });
+            }
+            .at(&self.end));
         }
 
         if argc > max {
-            return Err(ParseError::TooManyArgs {
+            let extra = self.args[max].1.to(&self.args[argc - 1].1);
+
+            return Err(ParseErrorKind::TooManyArgs {
                 got: argc,
                 expected: max,
-            });
+            }
+            .at(&extra));
         }
 
         Ok(())
@@ -39,14 +58,15 @@ impl Arguments {
     }
 
     pub fn fetch_nth_as_ident(&self, n: usize) -> ArgFetchResult<Identifier> {
-        let Some(expr) = self.0.get(n) else {
-            return ArgFetchResult::Missing;
+        let Some((expr, span)) = self.args.get(n) else {
+            return ArgFetchResult::Missing(self.end.clone());
         };
 
         let Some(ident) = expr.clone().into_ident() else {
             return ArgFetchResult::InvalidType {
                 got: expr.to_string(),
                 expected: "Identifier".into(),
+                span: span.clone(),
             };
         };
 
@@ -54,23 +74,56 @@ impl Arguments {
     }
 
     pub fn fetch_nth_as_any(&self, n: usize) -> ArgFetchResult<Expression> {
-        if let Some(expr) = self.0.get(n) {
+        if let Some((expr, _)) = self.args.get(n) {
             return ArgFetchResult::Found(expr.clone());
         }
 
-        ArgFetchResult::Missing
+        ArgFetchResult::Missing(self.end.clone())
     }
 
     pub fn fetch_nth_as_number(&self, n: usize) -> ArgFetchResult<Number> {
         match self.fetch_nth_as_any(n) {
-            ArgFetchResult::Missing => ArgFetchResult::Missing,
-            ArgFetchResult::InvalidType { got, expected } => {
-                ArgFetchResult::InvalidType { got, expected }
-            }
+            ArgFetchResult::Missing(span) => ArgFetchResult::Missing(span),
+            ArgFetchResult::InvalidType {
+                got,
+                expected,
+                span,
+            } => ArgFetchResult::InvalidType {
+                got,
+                expected,
+                span,
+            },
             ArgFetchResult::Found(Expression::Number(n)) => ArgFetchResult::Found(n),
             ArgFetchResult::Found(invalid) => ArgFetchResult::InvalidType {
                 got: invalid.to_string(),
                 expected: "Number".into(),
+                span: self.args[n].1.clone(),
+            },
+        }
+    }
+
+    pub fn fetch_nth_as_jump_target(&self, n: usize) -> ArgFetchResult<JumpTarget> {
+        match self.fetch_nth_as_any(n) {
+            ArgFetchResult::Missing(span) => ArgFetchResult::Missing(span),
+            ArgFetchResult::InvalidType {
+                got,
+                expected,
+                span,
+            } => ArgFetchResult::InvalidType {
+                got,
+                expected,
+                span,
+            },
+            ArgFetchResult::Found(Expression::Number(offset)) => {
+                ArgFetchResult::Found(JumpTarget::Offset(offset))
+            }
+            ArgFetchResult::Found(Expression::Identifier(label)) => {
+                ArgFetchResult::Found(JumpTarget::Label(label))
+            }
+            ArgFetchResult::Found(invalid) => ArgFetchResult::InvalidType {
+                got: invalid.to_string(),
+                expected: "Number or Label".into(),
+                span: self.args[n].1.clone(),
             },
         }
     }
@@ -83,17 +136,30 @@ impl Arguments {
 
         downcast.map_or_else(
             |_| {
-                Err(ParseError::MismatchedTypes {
+                Err(ParseErrorKind::MismatchedTypes {
                     got: inner_type.into(),
                     expected: T::type_name().into(),
-                })
+                }
+                .at(&self.args[n].1))
             },
             |value| Ok(*value),
         )
     }
 
+    /// Fetches a string argument and parses it as a `FMT` template.
+    pub fn fetch_nth_as_template(&self, n: usize) -> Result<Template, ParseError> {
+        let text = self.fetch_nth::<Text>(n)?;
+
+        Template::parse(&text).map_err(|kind| kind.at(&self.args[n].1))
+    }
+
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.args.len()
+    }
+
+    /// Returns the locations of all arguments.
+    pub fn spans(&self) -> Vec<Span> {
+        self.args.iter().map(|(_, span)| span.clone()).collect()
     }
 }
 
@@ -101,26 +167,24 @@ impl<T> ArgFetchResult<T> {
     pub fn into_parse_err(self) -> Result<T, ParseError> {
         match self {
             Self::Found(value) => Ok(value),
-            Self::Missing => Err(ParseError::MissingArg),
-            Self::InvalidType { got, expected } => {
-                Err(ParseError::MismatchedTypes { got, expected })
-            }
+            Self::Missing(span) => Err(ParseErrorKind::MissingArg.at(&span)),
+            Self::InvalidType {
+                got,
+                expected,
+                span,
+            } => Err(ParseErrorKind::MismatchedTypes { got, expected }.at(&span)),
         }
     }
 
     pub fn into_optional(self) -> Result<Option<T>, ParseError> {
         match self {
-            Self::Missing => Ok(None),
-            Self::InvalidType { got, expected } => {
-                Err(ParseError::MismatchedTypes { got, expected })
-            }
+            Self::Missing(..) => Ok(None),
+            Self::InvalidType {
+                got,
+                expected,
+                span,
+            } => Err(ParseErrorKind::MismatchedTypes { got, expected }.at(&span)),
             Self::Found(obj) => Ok(Some(obj)),
         }
     }
 }
-
-impl From<Vec<Expression>> for Arguments {
-    fn from(value: Vec<Expression>) -> Self {
-        Self(value)
-    }
-}
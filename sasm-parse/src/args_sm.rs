@@ -1,27 +1,100 @@
-use crate::{error::ParseError, expression::Expression};
-use itertools::Itertools;
-use std::str::Chars;
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    expression::Expression,
+    span::Span,
+};
+use std::{
+    iter::{Enumerate, Peekable},
+    ops::Range,
+    str::Chars,
+};
+
+type CharCursor<'a> = Peekable<Enumerate<Chars<'a>>>;
+/// An error inside a string literal, with the columns of the invalid part if known.
+type StringError = (ParseErrorKind, Option<Range<usize>>);
+
+/// Characters which start a comment spanning until the end of the line.
+pub const COMMENT_CHARS: [char; 2] = [';', '#'];
 
 pub struct ArgParserStateMachine;
 
 impl ArgParserStateMachine {
+    /// Splits a line into code and a trailing comment _(without the comment character)_.
+    /// Comment characters inside string literals are not treated as comments.
+    pub fn split_comment(line: &str) -> (&str, Option<&str>) {
+        let mut quote = None;
+        let mut escape = false;
+
+        for (i, ch) in line.char_indices() {
+            match quote {
+                Some(_) if escape => escape = false,
+                Some(_) if ch == '\\' => escape = true,
+                Some(qt) if ch == qt => quote = None,
+                Some(_) => (),
+                None if ch == '"' || ch == '\'' => quote = Some(ch),
+                None if COMMENT_CHARS.contains(&ch) => {
+                    return (&line[..i], Some(&line[i + ch.len_utf8()..]));
+                }
+                None => (),
+            }
+        }
+
+        (line, None)
+    }
+
+    #[cfg(test)]
     pub fn parse_args(raw: &str) -> Result<Vec<Expression>, ParseError> {
+        let parsed = Self::parse_args_spanned(raw, &Span::default())?;
+
+        Ok(parsed.into_iter().map(|(expr, _)| expr).collect())
+    }
+
+    /// Parses a comma-separated list of arguments. Whitespace around an argument is ignored.
+    ///
+    /// `origin` is the location of the first character of `raw`, it's used to compute
+    /// the span of each argument.
+    pub fn parse_args_spanned(
+        raw: &str,
+        origin: &Span,
+    ) -> Result<Vec<(Expression, Span)>, ParseError> {
         let mut expressions = Vec::new();
-        let mut chars_iter = raw.chars();
+        let mut chars_iter = raw.chars().enumerate().peekable();
         let mut buffer = String::new();
+        let mut arg_start = 0;
+        let mut arg_end = None;
+        let raw_len = raw.chars().count();
+        let at = |columns: std::ops::Range<usize>| {
+            let base = origin.columns.start;
+            origin.with_columns(base + columns.start..base + columns.end)
+        };
+
+        while let Some((pos, ch)) = chars_iter.next() {
+            if arg_end.is_some() && !ch.is_whitespace() && ch != ',' {
+                return Err(ParseErrorKind::UnexpectedToken(ch).at(&at(pos..pos + 1)));
+            }
 
-        while let Some(ch) = chars_iter.next() {
             match ch {
+                ch if ch.is_whitespace() => {
+                    if buffer.is_empty() {
+                        arg_start = pos + 1;
+                    } else if arg_end.is_none() {
+                        arg_end = Some(pos);
+                    }
+                }
                 '-' | '0'..='9' | '.' => {
                     buffer.push(ch);
 
                     collect_rest(chars_iter.by_ref(), char::is_ascii_digit, &mut buffer);
                 }
                 '"' | '\'' => {
-                    buffer = Self::parse_string(chars_iter.by_ref(), ch);
+                    let text = Self::parse_string(chars_iter.by_ref(), ch)
+                        .map_err(|(kind, columns)| kind.at(&at(columns.unwrap_or(pos..raw_len))))?;
 
-                    buffer.insert(0, ch);
                     buffer.push(ch);
+                    buffer.push_str(&text);
+                    buffer.push(ch);
+                    // Only whitespace or a comma may follow the closing quote
+                    arg_end = Some(chars_iter.peek().map_or(raw_len, |(pos, _)| *pos));
                 }
                 'a'..='z' | 'A'..='Z' | '_' => {
                     buffer.push(ch);
@@ -33,51 +106,94 @@ impl ArgParserStateMachine {
                     );
                 }
                 ',' => {
-                    expressions.push(Expression::try_from(buffer.as_str())?);
+                    let span = at(arg_start..arg_end.take().unwrap_or(pos));
+                    let expr = Expression::try_from(buffer.as_str()).map_err(|e| e.or_at(&span))?;
+
+                    expressions.push((expr, span));
                     buffer.clear();
+                    arg_start = pos + 1;
                 }
                 other => {
-                    return Err(ParseError::UnexpectedToken(other));
+                    return Err(ParseErrorKind::UnexpectedToken(other).at(&at(pos..pos + 1)));
                 }
             }
         }
 
         if !buffer.is_empty() {
-            let expr = Expression::try_from(buffer.as_str())?;
-            expressions.push(expr);
+            let span = at(arg_start..arg_end.unwrap_or(raw_len));
+            let expr = Expression::try_from(buffer.as_str()).map_err(|e| e.or_at(&span))?;
+            expressions.push((expr, span));
         }
 
         Ok(expressions)
     }
 
-    fn parse_string<I: Iterator<Item = char>>(chars: I, qt: char) -> String {
+    /// Parses the rest of a string literal started by the quote `qt`, decoding escape sequences.
+    ///
+    /// On error, returns the columns of the invalid escape sequence, or `None` if the string is not terminated.
+    fn parse_string(chars: &mut CharCursor<'_>, qt: char) -> Result<String, StringError> {
         let mut buffer = String::new();
-        let mut escape = false;
 
-        for ch in chars {
-            if ch == '\\' {
-                escape = true;
+        while let Some((pos, ch)) = chars.next() {
+            match ch {
+                '\\' => buffer.push(Self::parse_escape(chars, pos)?),
+                ch if ch == qt => return Ok(buffer),
+                ch => buffer.push(ch),
             }
+        }
 
-            if ch == qt && !escape {
-                return buffer;
-            }
+        Err((ParseErrorKind::MissingStringEndQuote, None))
+    }
 
-            buffer.push(ch);
-        }
+    /// Decodes a single escape sequence after a backslash at `start`.
+    fn parse_escape(chars: &mut CharCursor<'_>, start: usize) -> Result<char, StringError> {
+        let Some((pos, ch)) = chars.next() else {
+            return Err((ParseErrorKind::MissingStringEndQuote, None));
+        };
+        let invalid = |sequence: String, end: usize| {
+            (ParseErrorKind::IllegalEscape(sequence), Some(start..end))
+        };
+
+        match ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            '\\' | '"' | '\'' => Ok(ch),
+            'u' => {
+                let mut sequence = String::from("\\u");
+                let mut end = pos + 1;
+
+                while let Some((pos, ch)) = chars.next_if(|(_, ch)| *ch != '"' && *ch != '\'') {
+                    sequence.push(ch);
+                    end = pos + 1;
+
+                    if ch == '}' {
+                        break;
+                    }
+                }
 
-        buffer
+                sequence
+                    .strip_prefix("\\u{")
+                    .and_then(|rest| rest.strip_suffix('}'))
+                    .filter(|hex| (1..=6).contains(&hex.len()))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(sequence.clone(), end))
+            }
+            other => Err(invalid(format!("\\{other}"), pos + 1)),
+        }
     }
 }
 
-fn collect_rest<F: FnMut(&char) -> bool>(it: &mut Chars<'_>, predicate: F, output: &mut String) {
-    it.take_while_ref(predicate).for_each(|ch| output.push(ch));
+fn collect_rest<F: Fn(&char) -> bool>(it: &mut CharCursor<'_>, predicate: F, output: &mut String) {
+    while let Some((_, ch)) = it.next_if(|(_, ch)| predicate(ch)) {
+        output.push(ch);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::ArgParserStateMachine;
-    use crate::{expression::Expression, ident::Identifier};
+    use crate::{error::ParseErrorKind, expression::Expression, ident::Identifier, span::Span};
 
     #[test]
     fn parse_numbers() {
@@ -205,4 +321,129 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn parse_escapes() {
+        let parsed =
+            ArgParserStateMachine::parse_args(r#""a\"b\n\t\\",'it\'s',"\u{48}\u{1F600}""#).unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![
+                Expression::make_string("a\"b\n\t\\"),
+                Expression::make_string("it's"),
+                Expression::make_string("H\u{1F600}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_errors() {
+        let origin = Span::new(None, 1, 4..4);
+        let unterminated =
+            ArgParserStateMachine::parse_args_spanned("x,'abc", &origin).unwrap_err();
+        let bad_escape =
+            ArgParserStateMachine::parse_args_spanned(r#""a\qb""#, &origin).unwrap_err();
+        let bad_unicode =
+            ArgParserStateMachine::parse_args_spanned(r#""\u{110000}""#, &origin).unwrap_err();
+
+        assert!(matches!(
+            unterminated.kind,
+            ParseErrorKind::MissingStringEndQuote
+        ));
+        assert_eq!(unterminated.span.unwrap().columns, 6..10);
+        assert!(matches!(bad_escape.kind, ParseErrorKind::IllegalEscape(ref seq) if seq == "\\q"));
+        assert_eq!(bad_escape.span.unwrap().columns, 6..8);
+        assert!(matches!(
+            bad_unicode.kind,
+            ParseErrorKind::IllegalEscape(..)
+        ));
+    }
+
+    #[test]
+    fn parse_arg_spans() {
+        let origin = Span::new(None, 3, 4..4);
+        let parsed = ArgParserStateMachine::parse_args_spanned("x,'hi',12", &origin).unwrap();
+        let columns: Vec<_> = parsed.into_iter().map(|(_, span)| span.columns).collect();
+
+        assert_eq!(columns, vec![4..5, 6..10, 11..13]);
+
+        // Columns count characters, so non-ASCII text doesn't shift the following arguments
+        let parsed = ArgParserStateMachine::parse_args_spanned("x,'ü😀',12", &origin).unwrap();
+        let columns: Vec<_> = parsed.into_iter().map(|(_, span)| span.columns).collect();
+
+        assert_eq!(columns, vec![4..5, 6..10, 11..13]);
+    }
+
+    #[test]
+    fn parse_spaced_args() {
+        let origin = Span::new(None, 1, 0..0);
+        let parsed =
+            ArgParserStateMachine::parse_args_spanned(" x , 'a b',\t-2 ", &origin).unwrap();
+        let (exprs, spans): (Vec<_>, Vec<_>) = parsed.into_iter().unzip();
+
+        assert_eq!(
+            exprs,
+            vec![
+                Expression::Identifier(Identifier::new("x")),
+                Expression::make_string("a b"),
+                Expression::Number(-2),
+            ]
+        );
+        assert_eq!(
+            spans
+                .into_iter()
+                .map(|span| span.columns)
+                .collect::<Vec<_>>(),
+            vec![1..2, 5..10, 12..14]
+        );
+
+        let err = ArgParserStateMachine::parse_args_spanned("x y,1", &origin).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedToken('y')));
+        assert_eq!(err.span.unwrap().columns, 2..3);
+    }
+
+    #[test]
+    fn text_after_string() {
+        let origin = Span::new(None, 1, 0..0);
+        let parsed = ArgParserStateMachine::parse_args_spanned("'a' ,1", &origin).unwrap();
+        assert_eq!(parsed[0].1.columns, 0..3);
+
+        let err = ArgParserStateMachine::parse_args_spanned(r#"x,"a""b""#, &origin).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedToken('"')));
+        assert_eq!(err.span.unwrap().columns, 5..6);
+
+        let err = ArgParserStateMachine::parse_args_spanned("'a'b", &origin).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedToken('b')));
+    }
+
+    #[test]
+    fn unexpected_token_span() {
+        let origin = Span::new(None, 2, 4..4);
+        let err = ArgParserStateMachine::parse_args_spanned("x$,1", &origin).unwrap_err();
+        let span = err.span.unwrap();
+
+        assert_eq!(span.line, 2);
+        assert_eq!(span.columns, 5..6);
+    }
+
+    #[test]
+    fn split_comments() {
+        assert_eq!(
+            ArgParserStateMachine::split_comment("MOV x,1 ; set x"),
+            ("MOV x,1 ", Some(" set x"))
+        );
+        assert_eq!(
+            ArgParserStateMachine::split_comment("# full line"),
+            ("", Some(" full line"))
+        );
+        assert_eq!(
+            ArgParserStateMachine::split_comment("SAY \"a;b#c\" #done"),
+            ("SAY \"a;b#c\" ", Some("done"))
+        );
+        assert_eq!(
+            ArgParserStateMachine::split_comment("SAY 'it\\'s;'"),
+            ("SAY 'it\\'s;'", None)
+        );
+    }
 }
@@ -1,11 +1,22 @@
+use crate::{expression::Number, span::Span};
 use thiserror::Error;
 
+/// An error that occurred while parsing source code, optionally with its location.
 #[derive(Debug, Error)]
-pub enum ParseError {
+#[error("{kind}")]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseErrorKind {
     #[error("Invalid identifier: {0}")]
     IllegalIdentifier(String),
     #[error("Invalid expression: {0}")]
     IllegalExpression(String),
+    #[error("Expected an instruction")]
+    MissingInstruction,
     #[error("Invalid instruction: {0}")]
     IllegalInstruction(String),
     #[error("Missing separator for instruction-args")]
@@ -14,12 +25,57 @@ pub enum ParseError {
     MissingArg,
     #[error("Expected value of type {expected}, got {got}")]
     MismatchedTypes { got: String, expected: String },
+    #[error("Variable '{name}' has type {expected}, but is assigned a {got}")]
+    ConflictingAssignment {
+        name: String,
+        got: String,
+        expected: String,
+    },
     #[error("Missing end quotes for string expression")]
     MissingStringEndQuote,
+    #[error("Invalid escape sequence: `{0}`")]
+    IllegalEscape(String),
+    #[error("Invalid format string: {0}")]
+    IllegalFormat(String),
     #[error("Expected at least {expected} arguments, got {got}")]
     NotEnoughArgs { got: usize, expected: usize },
     #[error("Expected at most {expected} arguments, got {got}")]
     TooManyArgs { got: usize, expected: usize },
     #[error("Unexpected token: `{0}`")]
     UnexpectedToken(char),
+    #[error("Label '{0}' has already been defined")]
+    DuplicateLabel(String),
+    #[error("Undefined label '{0}'")]
+    UndefinedLabel(String),
+    #[error("Jump by {0} instructions leaves the program")]
+    JumpOutOfBounds(Number),
+}
+
+impl ParseError {
+    /// Attaches a location to the error, unless it already has one.
+    #[must_use]
+    pub fn or_at(mut self, span: &Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span.clone());
+        }
+
+        self
+    }
+}
+
+impl ParseErrorKind {
+    /// Turns this error kind into a [`ParseError`] located at `span`.
+    #[must_use]
+    pub fn at(self, span: &Span) -> ParseError {
+        ParseError {
+            kind: self,
+            span: Some(span.clone()),
+        }
+    }
+}
+
+impl From<ParseErrorKind> for ParseError {
+    fn from(kind: ParseErrorKind) -> Self {
+        Self { kind, span: None }
+    }
 }
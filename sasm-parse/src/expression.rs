@@ -1,7 +1,13 @@
-use crate::{error::ParseError, ident::Identifier};
+use crate::{
+    args_sm::ArgParserStateMachine,
+    error::{ParseError, ParseErrorKind},
+    ident::Identifier,
+    span::Span,
+};
 use std::{
     any::Any,
-    fmt::{self, Display},
+    collections::BTreeMap,
+    fmt::{self, Display, Write},
     mem,
     rc::Rc,
 };
@@ -9,9 +15,16 @@ use std::{
 /// A 64-bit signed integer [`i64`].
 pub type Number = i64;
 /// A dynamically-allocated string of characters that can be cheaply cloned.
+///
+/// Instructions count and index strings by characters _(Unicode scalar values, ie. [`char`]s)_, never by bytes.
 pub type Text = Rc<String>;
 /// A 32-bit floating point number [`f32`].
 pub type Float = f32;
+/// A dynamically-allocated list of values that can be cheaply cloned.
+pub type List = Rc<Vec<Expression>>;
+/// A dynamically-allocated map from strings to values that can be cheaply cloned.
+/// Keys are kept in sorted order.
+pub type Map = Rc<BTreeMap<String, Expression>>;
 
 /// An expression.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -22,6 +35,10 @@ pub enum Expression {
     String(Text),
     /// A 32-bit floating point number [`f32`].
     Float(Float),
+    /// A list of values. All elements of a list have the same type.
+    List(List),
+    /// A map from strings to values. All values of a map have the same type.
+    Map(Map),
     /// An identifier
     Identifier(Identifier),
 }
@@ -35,14 +52,60 @@ impl Display for Expression {
             Self::Number(num) => write!(f, "<{num}>"),
             Self::String(text) => write!(f, "<'{text}'>"),
             Self::Float(val) => write!(f, "<{val}>"),
+            Self::List(list) => write!(f, "<{} elements>", list.len()),
+            Self::Map(map) => write!(f, "<{} entries>", map.len()),
+        }
+    }
+}
+
+/// The only float literal without digits. It's reserved, so it can't name a variable.
+pub(crate) const NAN_LITERAL: &str = "NaN";
+
+/// Displays an [`Expression`] the way it's written in source code, see [`Expression::source`].
+pub struct Source<'a>(&'a Expression);
+
+impl Display for Source<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Expression::Identifier(ident) => ident.fmt(f),
+            Expression::Number(num) => num.fmt(f),
+            Expression::String(text) => write_quoted(f, text),
+            // Infinity has no literal, but a literal too large for a float is read back as infinity
+            Expression::Float(val) if val.is_infinite() => {
+                let sign = if val.is_sign_negative() { "-" } else { "" };
+                write!(f, "{sign}1e39")
+            }
+            Expression::Float(val) if val.is_nan() => f.write_str(NAN_LITERAL),
+            // `Debug` always keeps the decimal point, so the float isn't read back as a number
+            Expression::Float(val) => write!(f, "{val:?}"),
+            other @ (Expression::List(..) | Expression::Map(..)) => other.fmt(f),
+        }
+    }
+}
+
+/// Writes a string literal in double quotes, escaping quotes, backslashes and control characters.
+pub(crate) fn write_quoted(f: &mut impl Write, text: &str) -> fmt::Result {
+    f.write_char('"')?;
+
+    for ch in text.chars() {
+        match ch {
+            '"' | '\\' => write!(f, "\\{ch}")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            ch if ch.is_control() => write!(f, "\\u{{{:x}}}", u32::from(ch))?,
+            ch => f.write_char(ch)?,
         }
     }
+
+    f.write_char('"')
 }
 
 impl Expression {
     pub const NUMBER_TYPE_NAME: &'static str = "Number";
-    pub const FLOAT_TYPE_NAME: &'static str = "Number";
+    pub const FLOAT_TYPE_NAME: &'static str = "Float";
     pub const STRING_TYPE_NAME: &'static str = "String";
+    pub const LIST_TYPE_NAME: &'static str = "List";
+    pub const MAP_TYPE_NAME: &'static str = "Map";
     pub const IDENT_TYPE_NAME: &'static str = "Identifier";
 
     /// Creates a string expression from a string.
@@ -76,6 +139,18 @@ impl Expression {
         Self::Float(0.0)
     }
 
+    /// Creates a list expression containing an empty list.
+    #[must_use]
+    pub fn empty_list() -> Self {
+        Self::List(Rc::new(Vec::new()))
+    }
+
+    /// Creates a map expression containing an empty map.
+    #[must_use]
+    pub fn empty_map() -> Self {
+        Self::Map(Rc::new(BTreeMap::new()))
+    }
+
     #[must_use]
     pub fn rewrap_string(rcs: Text) -> Self {
         Self::String(rcs)
@@ -104,6 +179,43 @@ impl Expression {
             Self::Number(..) => Self::NUMBER_TYPE_NAME,
             Self::String(..) => Self::STRING_TYPE_NAME,
             Self::Float(..) => Self::FLOAT_TYPE_NAME,
+            Self::List(..) => Self::LIST_TYPE_NAME,
+            Self::Map(..) => Self::MAP_TYPE_NAME,
+        }
+    }
+
+    /// Returns a value which displays this expression as source code, so that parsing the output
+    /// gives back the same expression. Strings are always written in double quotes.
+    ///
+    /// Lists and maps have no literal syntax, they are displayed like [`Display`] does.
+    ///
+    /// ```rust
+    /// # use sasm_parse::expression::Expression;
+    /// assert_eq!(Expression::make_string("a \"b\"").source().to_string(), r#""a \"b\"""#);
+    /// assert_eq!(Expression::Float(2.0).source().to_string(), "2.0");
+    /// ```
+    #[must_use]
+    pub const fn source(&self) -> Source<'_> {
+        Source(self)
+    }
+
+    /// Parses a single literal or identifier written like an argument of an instruction.
+    /// Unlike [`Expression::try_from`], escape sequences in strings are decoded.
+    ///
+    /// ```rust
+    /// # use sasm_parse::expression::Expression;
+    /// assert_eq!(Expression::parse(r#" "a\n" "#).unwrap(), Expression::make_string("a\n"));
+    /// assert!(Expression::parse("1,2").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the text isn't exactly one valid argument.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut args = ArgParserStateMachine::parse_args_spanned(source, &Span::default())?;
+
+        match args.pop() {
+            Some((expr, _)) if args.is_empty() => Ok(expr),
+            _ => Err(ParseErrorKind::IllegalExpression(source.trim().into()).into()),
         }
     }
 
@@ -120,6 +232,8 @@ impl Expression {
             Self::Number(num) => Box::new(num),
             Self::String(string) => Box::new(string),
             Self::Float(val) => Box::new(val),
+            Self::List(list) => Box::new(list),
+            Self::Map(map) => Box::new(map),
         }
     }
 }
@@ -132,8 +246,15 @@ impl TryFrom<&str> for Expression {
             return Ok(Self::Number(value));
         }
 
-        if let Ok(value) = value.parse::<Float>() {
-            return Ok(Self::Float(value));
+        if value == NAN_LITERAL {
+            return Ok(Self::Float(Float::NAN));
+        }
+
+        // Without a digit, it's an identifier like `inf` or `nan`, not a float
+        if value.contains(|ch: char| ch.is_ascii_digit()) {
+            if let Ok(value) = value.parse::<Float>() {
+                return Ok(Self::Float(value));
+            }
         }
 
         if (value.starts_with('\'') && value.ends_with('\''))
@@ -147,6 +268,6 @@ impl TryFrom<&str> for Expression {
             return Ok(Self::Identifier(ident));
         }
 
-        Err(Self::Error::IllegalExpression(value.into()))
+        Err(ParseErrorKind::IllegalExpression(value.into()).into())
     }
 }
@@ -1,4 +1,7 @@
-use crate::error::ParseError;
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    expression::NAN_LITERAL,
+};
 use std::{fmt::Display, rc::Rc};
 
 /// An identifier (variable name).
@@ -30,8 +33,9 @@ impl TryFrom<&str> for Identifier {
         if !value
             .chars()
             .all(|ch| ch.is_ascii_alphabetic() || ch == '_')
+            || value == NAN_LITERAL
         {
-            return Err(Self::Error::IllegalIdentifier(value.into()));
+            return Err(ParseErrorKind::IllegalIdentifier(value.into()).into());
         }
 
         Ok(Self(Rc::new(value.into())))
@@ -1,6 +1,10 @@
 macro_rules! define_instr_names {
     ($($name:ident),*) => {
         $(pub const $name: &str = stringify!($name);)*
+
+        /// All mnemonics, in the order they are defined.
+        #[cfg(test)]
+        pub const ALL: &[&str] = &[$($name),*];
     };
 }
 
@@ -16,16 +20,52 @@ define_instr_names!(
     MUL,
     DIV,
     POW,
+    SQRT,
+    SIN,
+    COS,
+    LOG,
+    FLOOR,
+    CEIL,
+    ROUND,
     CMP,
     JNE,
     JEQ,
+    JLT,
+    JGT,
+    JLE,
+    JGE,
     JMP,
+    CALL,
+    RET,
     RNV,
     RSV,
     RNG,
     PSH,
     POP,
+    LST,
+    GET,
+    SET,
+    INS,
+    RMV,
+    MAP,
+    HAS,
+    KEY,
     FMT,
+    NUM,
+    FLT,
+    STR,
+    TYPEOF,
+    SUBSTR,
+    FIND,
+    REPLACE,
+    UPPER,
+    LOWER,
+    TRIM,
+    REVERSE,
+    REPEAT,
+    SPLIT,
+    ORD,
+    CHR,
     SAY,
     LEN,
     CLR,
@@ -0,0 +1,187 @@
+use crate::{
+    args_sm::ArgParserStateMachine,
+    error::{ParseError, ParseErrorKind},
+    expression::Number,
+    ident::Identifier,
+    span::Span,
+    Instruction, SpannedInstruction,
+};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    rc::Rc,
+};
+
+/// The destination of a jump instruction.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JumpTarget {
+    /// Skips the given number of instructions _(relative to the jump)_.
+    Offset(Number),
+    /// Continues at the first instruction after the given label.
+    Label(Identifier),
+}
+
+/// A label definition (eg. `loop:`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub name: Identifier,
+    pub span: Span,
+}
+
+/// A single parsed line of source code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    /// An empty line, or a line containing only a comment.
+    Blank,
+    /// A label definition.
+    Label(Label),
+    /// An instruction.
+    Instruction(SpannedInstruction),
+}
+
+/// Maps label names to the index of the instruction they point to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelTable(HashMap<Identifier, usize>);
+
+impl Display for JumpTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Offset(offset) => offset.fmt(f),
+            Self::Label(label) => label.fmt(f),
+        }
+    }
+}
+
+impl Line {
+    /// Parses a single line of source code, which may be a label definition or an instruction.
+    ///
+    /// # Errors
+    /// Returns a [`ParseError`] pointing at the offending part of `line`.
+    pub fn parse(line: &str, file: Option<Rc<str>>, line_no: usize) -> Result<Self, ParseError> {
+        let (code, _comment) = ArgParserStateMachine::split_comment(line);
+
+        let Some(name) = Self::label_name(code) else {
+            return Ok(Instruction::parse_spanned(line, file, line_no)?
+                .map_or(Self::Blank, Self::Instruction));
+        };
+
+        let indent = code.chars().take_while(|ch| ch.is_whitespace()).count();
+        let span = Span::new(file, line_no, indent..indent + name.chars().count());
+        let name = Identifier::try_from(name).map_err(|e| e.or_at(&span))?;
+
+        Ok(Self::Label(Label { name, span }))
+    }
+
+    /// Returns `true` if a line is meant to define a label, even if the label name is invalid.
+    pub(crate) fn is_label(line: &str) -> bool {
+        Self::label_name(ArgParserStateMachine::split_comment(line).0).is_some()
+    }
+
+    fn label_name(code: &str) -> Option<&str> {
+        code.trim().strip_suffix(':')
+    }
+}
+
+impl LabelTable {
+    /// Collects all label definitions and checks that every jump refers to a defined label.
+    ///
+    /// Each label points to the first instruction following it. A label at the end of the
+    /// program points one past the last instruction.
+    ///
+    /// # Errors
+    /// Returns every duplicate label definition and every jump to an undefined label.
+    pub fn resolve(lines: &[Line]) -> Result<Self, Vec<ParseError>> {
+        let lines: Vec<_> = lines.iter().map(Ok).collect();
+
+        Self::resolve_partial(&lines)
+    }
+
+    /// Like [`LabelTable::resolve`], but lines may have failed to parse. `Err(label)` stands for
+    /// such a line, with `label` being `true` if it was meant to define a label.
+    ///
+    /// Invalid instructions still move the following labels. If a label definition is invalid,
+    /// jumps to undefined labels aren't reported, as they might refer to it.
+    pub(crate) fn resolve_partial(lines: &[Result<&Line, bool>]) -> Result<Self, Vec<ParseError>> {
+        let mut table = HashMap::new();
+        let mut errors = Vec::new();
+        let mut instr_count = 0;
+
+        for line in lines {
+            match line {
+                Ok(Line::Blank) | Err(true) => (),
+                Ok(Line::Instruction(..)) | Err(false) => instr_count += 1,
+                Ok(Line::Label(label)) => {
+                    if table.insert(label.name.clone(), instr_count).is_some() {
+                        errors.push(
+                            ParseErrorKind::DuplicateLabel(label.name.to_string()).at(&label.span),
+                        );
+                    }
+                }
+            }
+        }
+
+        let labels_complete = !lines.contains(&Err(true));
+
+        for line in lines {
+            let Ok(Line::Instruction(instr)) = line else {
+                continue;
+            };
+
+            if let Some(JumpTarget::Label(name)) = instr.instr.jump_target() {
+                if labels_complete && !table.contains_key(name) {
+                    errors.push(
+                        ParseErrorKind::UndefinedLabel(name.to_string())
+                            .at(&instr.span.arg_or_full(0)),
+                    );
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self(table))
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the index of the instruction the label points to.
+    #[must_use]
+    pub fn get(&self, name: &Identifier) -> Option<usize> {
+        self.0.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LabelTable, Line};
+    use crate::{error::ParseErrorKind, ident::Identifier};
+
+    fn parse_lines(source: &str) -> Vec<Line> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| Line::parse(line, None, i + 1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn labels_point_to_next_instruction() {
+        let lines = parse_lines("start:\nVAR x\n; comment\n\nloop:\nINC x\nJMP loop\nend:");
+        let table = LabelTable::resolve(&lines).unwrap();
+
+        assert_eq!(table.get(&Identifier::new("start")), Some(0));
+        assert_eq!(table.get(&Identifier::new("loop")), Some(1));
+        assert_eq!(table.get(&Identifier::new("end")), Some(3));
+    }
+
+    #[test]
+    fn undefined_and_duplicate_labels() {
+        let lines = parse_lines("a:\nJMP b\na:\nJEQ a");
+        let errors = LabelTable::resolve(&lines).unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(&errors[0].kind, ParseErrorKind::DuplicateLabel(name) if name == "a"));
+        assert!(matches!(&errors[1].kind, ParseErrorKind::UndefinedLabel(name) if name == "b"));
+        assert_eq!(errors[1].span.as_ref().unwrap().line, 2);
+    }
+}
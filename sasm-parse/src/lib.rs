@@ -6,11 +6,17 @@
 
 use args::Arguments;
 use args_sm::ArgParserStateMachine;
-use error::ParseError;
-use expression::{Expression, Number, Text};
+use error::{ParseError, ParseErrorKind};
+use expression::{Expression, Number};
 use ident::Identifier;
 use instr_names::*;
-use std::fmt::{self, Display};
+use label::JumpTarget;
+use span::{InstrSpan, Span};
+use std::{
+    fmt::{self, Display},
+    rc::Rc,
+};
+use template::Template;
 
 mod args;
 mod args_sm;
@@ -18,7 +24,14 @@ pub mod error;
 pub mod expression;
 pub mod ident;
 mod instr_names;
+pub mod label;
+pub mod lint;
+pub mod program;
+pub mod source_fmt;
+pub mod span;
+pub mod template;
 pub mod type_trait;
+pub mod typecheck;
 
 /// An executable operation that can be executed by an interpreter.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -43,15 +56,42 @@ pub enum Instruction {
     Divide(Identifier, Expression),
     /// Performes mathematical exponentiation on the given variable with the given exponent.
     Power(Identifier, Expression),
+    /// Replaces the float inside the given variable with its square root.
+    SquareRoot(Identifier),
+    /// Replaces the float inside the given variable with its sine _(in radians)_.
+    Sine(Identifier),
+    /// Replaces the float inside the given variable with its cosine _(in radians)_.
+    Cosine(Identifier),
+    /// Replaces the float inside the given variable with its natural logarithm.
+    Logarithm(Identifier),
+    /// Rounds the float inside the given variable down.
+    Floor(Identifier),
+    /// Rounds the float inside the given variable up.
+    Ceil(Identifier),
+    /// Rounds the float inside the given variable to the nearest integer. Halfway cases are rounded away from zero.
+    Round(Identifier),
     /// Compares the value inside the given variable with an expression (possibly another variable).
-    /// The result of this comparion is saved by the interpreter into some internal variable.
+    /// The result of this comparion _(less, equal, greater or unordered)_ is saved by the interpreter
+    /// and used by conditional jumps.
     Compare(Identifier, Expression),
-    /// Skips a given number of instructions if the last comparison was `true`.
-    JumpEqual(Number),
-    /// Skips a given number of instructions if the last comparison was `false`.
-    JumpNotEqual(Number),
-    /// Skips a given number of instructions.
-    Jump(Number),
+    /// Jumps to the given target if the last comparison found the values equal.
+    JumpEqual(JumpTarget),
+    /// Jumps to the given target if the last comparison did not find the values equal.
+    JumpNotEqual(JumpTarget),
+    /// Jumps to the given target if the first value of the last comparison was less than the second.
+    JumpLess(JumpTarget),
+    /// Jumps to the given target if the first value of the last comparison was greater than the second.
+    JumpGreater(JumpTarget),
+    /// Jumps to the given target if the first value of the last comparison was less than or equal to the second.
+    JumpLessEqual(JumpTarget),
+    /// Jumps to the given target if the first value of the last comparison was greater than or equal to the second.
+    JumpGreaterEqual(JumpTarget),
+    /// Jumps to the given target.
+    Jump(JumpTarget),
+    /// Jumps to the given target, saving the position of the next instruction on the call stack.
+    Call(JumpTarget),
+    /// Returns to the position saved by the last [`Instruction::Call`].
+    Return,
     /// Reads a number from `stdin` and saves it into the given variable.
     ReadNumericValue(Identifier),
     /// Reads a line from `stdin` and saves it into the given variable.
@@ -59,12 +99,68 @@ pub enum Instruction {
     /// Writes a random number into the given variable. Optionally, a _minimum_ and _maximum_ range can be specified.
     GenerateRandomNumber(Identifier, Option<Expression>, Option<Expression>),
     /// Pushes a string (or a string inside another variable) into the given variable.
+    /// If the variable contains a list, the value is appended to the list.
     Push(Identifier, Expression),
-    /// Pops a single character from a string inside the given variable.
+    /// Pops the last character from a string inside the given variable.
     /// Optionally you can specify another variable, which will contain the popped character as a single character string.
+    /// If the variable contains a list, its last element is popped.
     Pop(Identifier, Option<Identifier>),
-    /// Writes a formatted string into a varible. The format string uses the same syntax as Rust's [`format!()`].
-    Format(Identifier, Text),
+    /// Writes an empty list into the given variable.
+    CreateList(Identifier),
+    /// Reads the element at an index of a list (or the value of a key in a map, or a character of a string) into the given variable.
+    GetItem(Identifier, Identifier, Expression),
+    /// Replaces the element at an index of a list with a value.
+    /// For maps, the value is inserted under the given key.
+    SetItem(Identifier, Expression, Expression),
+    /// Inserts a value into a list at an index, shifting all elements after it.
+    InsertItem(Identifier, Expression, Expression),
+    /// Removes the element at an index of a list (or a key from a map).
+    /// Optionally you can specify another variable, which will contain the removed element.
+    RemoveItem(Identifier, Expression, Option<Identifier>),
+    /// Writes an empty map into the given variable.
+    CreateMap(Identifier),
+    /// Checks whether a map contains a key. The result is saved like the result of [`Instruction::Compare`]:
+    /// _equal_ if the key is present, _unordered_ otherwise. `JEQ` then jumps if the key is present.
+    HasKey(Identifier, Expression),
+    /// Writes a list of all keys of a map _(in sorted order)_ into the given variable.
+    Keys(Identifier, Identifier),
+    /// Converts a value into a number and writes it into the given variable.
+    /// Floats are truncated towards zero, strings are parsed.
+    IntoNumber(Identifier, Expression),
+    /// Converts a value into a float and writes it into the given variable. Strings are parsed.
+    IntoFloat(Identifier, Expression),
+    /// Converts a value into a string _(the same text `DMP` would print)_ and writes it into the given variable.
+    IntoString(Identifier, Expression),
+    /// Writes the name of the type of a value into the given variable.
+    TypeOf(Identifier, Expression),
+    /// Writes the characters of a string between a start index _(inclusive)_ and an optional end index
+    /// _(exclusive)_ into the given variable. Without an end index, the rest of the string is taken.
+    Substring(Identifier, Expression, Expression, Option<Expression>),
+    /// Writes the index of the first occurrence of a substring into the given variable, or `-1` if
+    /// the substring isn't found.
+    Find(Identifier, Expression, Expression),
+    /// Replaces all occurrences of a substring inside the string in the given variable.
+    Replace(Identifier, Expression, Expression),
+    /// Converts the string inside the given variable to uppercase.
+    Uppercase(Identifier),
+    /// Converts the string inside the given variable to lowercase.
+    Lowercase(Identifier),
+    /// Removes leading and trailing whitespace from the string inside the given variable.
+    Trim(Identifier),
+    /// Reverses the order of the characters of a string (or the elements of a list) inside the given variable.
+    Reverse(Identifier),
+    /// Repeats the string inside the given variable a number of times.
+    Repeat(Identifier, Expression),
+    /// Splits a string at every occurrence of a separator and writes the parts into the given variable as a list.
+    /// An empty separator splits the string into single characters.
+    Split(Identifier, Expression, Expression),
+    /// Writes the Unicode code point of a single character string into the given variable.
+    CharCode(Identifier, Expression),
+    /// Writes the character with the given Unicode code point into the given variable.
+    CharFromCode(Identifier, Expression),
+    /// Writes a formatted string into a varible. Placeholders like `{name}` or `{name:>8.2}` are replaced
+    /// with the values of variables, see [`Template`] for the syntax.
+    Format(Identifier, Template),
     /// Writes an expression to `stdout` **without newline**.
     Print(Expression),
     /// Resets a variable's value to it's default.
@@ -72,6 +168,7 @@ pub enum Instruction {
     /// For strings, it clears them - turning them into an empty string.
     Clear(Identifier),
     /// Calculates the length of an array-like object (eg. strings) and saves it into the given variable.
+    /// The length of a string is its number of characters.
     Length(Identifier, Expression),
     /// Stops execution for a given amount of time _(milliseconds)_.
     Sleep(Expression),
@@ -81,13 +178,22 @@ pub enum Instruction {
     Die(Number),
 }
 
-macro_rules! impl_display_for_instructions {
+/// An [`Instruction`] together with the location it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedInstruction {
+    pub instr: Instruction,
+    pub span: InstrSpan,
+}
+
+macro_rules! impl_mnemonic_for_instructions {
     ($($variant:ident => $var:ident),* $(,)?) => {
-        impl Display for Instruction {
-            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        impl Instruction {
+            /// Returns the mnemonic of this instruction (eg. `MOV`).
+            #[must_use]
+            pub const fn mnemonic(&self) -> &'static str {
                 match self {
                     $(
-                        Self::$variant(..) => write!(f, "{}", $var),
+                        Self::$variant { .. } => $var,
                     )*
                 }
             }
@@ -95,7 +201,170 @@ macro_rules! impl_display_for_instructions {
     };
 }
 
-impl_display_for_instructions! {
+/// An argument of an [`Instruction`] which can be written back as source code.
+trait SourceArg {
+    /// Appends the source form of this argument to `args`.
+    fn push_to(&self, args: &mut Vec<String>);
+}
+
+impl SourceArg for Identifier {
+    fn push_to(&self, args: &mut Vec<String>) {
+        args.push(self.to_string());
+    }
+}
+
+impl SourceArg for Expression {
+    fn push_to(&self, args: &mut Vec<String>) {
+        args.push(self.source().to_string());
+    }
+}
+
+impl SourceArg for JumpTarget {
+    fn push_to(&self, args: &mut Vec<String>) {
+        args.push(self.to_string());
+    }
+}
+
+impl SourceArg for Template {
+    fn push_to(&self, args: &mut Vec<String>) {
+        let mut quoted = String::new();
+        expression::write_quoted(&mut quoted, &self.to_string()).expect("writing to a string");
+        args.push(quoted);
+    }
+}
+
+impl<T: SourceArg> SourceArg for Option<T> {
+    fn push_to(&self, args: &mut Vec<String>) {
+        if let Some(arg) = self {
+            arg.push_to(args);
+        }
+    }
+}
+
+impl Instruction {
+    /// Returns the arguments of this instruction as they are written in source code.
+    #[must_use]
+    pub fn arguments(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |arg: &dyn SourceArg| arg.push_to(&mut args);
+
+        match self {
+            Self::Return => (),
+            Self::CreateVariable(var)
+            | Self::Increment(var)
+            | Self::Decrement(var)
+            | Self::SquareRoot(var)
+            | Self::Sine(var)
+            | Self::Cosine(var)
+            | Self::Logarithm(var)
+            | Self::Floor(var)
+            | Self::Ceil(var)
+            | Self::Round(var)
+            | Self::ReadNumericValue(var)
+            | Self::ReadStringValue(var)
+            | Self::CreateList(var)
+            | Self::CreateMap(var)
+            | Self::Uppercase(var)
+            | Self::Lowercase(var)
+            | Self::Trim(var)
+            | Self::Reverse(var)
+            | Self::Clear(var)
+            | Self::Delete(var) => push(var),
+            Self::Dump(expr) | Self::Print(expr) | Self::Sleep(expr) => push(expr),
+            Self::JumpEqual(target)
+            | Self::JumpNotEqual(target)
+            | Self::JumpLess(target)
+            | Self::JumpGreater(target)
+            | Self::JumpLessEqual(target)
+            | Self::JumpGreaterEqual(target)
+            | Self::Jump(target)
+            | Self::Call(target) => push(target),
+            Self::Move(var, expr)
+            | Self::Add(var, expr)
+            | Self::Subtract(var, expr)
+            | Self::Multiply(var, expr)
+            | Self::Divide(var, expr)
+            | Self::Power(var, expr)
+            | Self::Compare(var, expr)
+            | Self::Push(var, expr)
+            | Self::HasKey(var, expr)
+            | Self::IntoNumber(var, expr)
+            | Self::IntoFloat(var, expr)
+            | Self::IntoString(var, expr)
+            | Self::TypeOf(var, expr)
+            | Self::Repeat(var, expr)
+            | Self::CharCode(var, expr)
+            | Self::CharFromCode(var, expr)
+            | Self::Length(var, expr) => {
+                push(var);
+                push(expr);
+            }
+            Self::SetItem(var, a, b)
+            | Self::InsertItem(var, a, b)
+            | Self::Find(var, a, b)
+            | Self::Replace(var, a, b)
+            | Self::Split(var, a, b) => {
+                push(var);
+                push(a);
+                push(b);
+            }
+            Self::GenerateRandomNumber(var, min, max) => {
+                push(var);
+                push(min);
+                push(max);
+            }
+            Self::Pop(var, dst) => {
+                push(var);
+                push(dst);
+            }
+            Self::GetItem(dst, src, key) => {
+                push(dst);
+                push(src);
+                push(key);
+            }
+            Self::RemoveItem(var, key, dst) => {
+                push(var);
+                push(key);
+                push(dst);
+            }
+            Self::Keys(dst, map) => {
+                push(dst);
+                push(map);
+            }
+            Self::Substring(dst, src, start, end) => {
+                push(dst);
+                push(src);
+                push(start);
+                push(end);
+            }
+            Self::Format(dst, template) => {
+                push(dst);
+                push(template);
+            }
+            // `DIE` without an exit code exits with `0`
+            Self::Die(0) => (),
+            Self::Die(code) => push(&Expression::Number(*code)),
+        }
+
+        args
+    }
+}
+
+impl Display for Instruction {
+    /// Writes the instruction back as source code, in the canonical form: an uppercase mnemonic
+    /// followed by the arguments separated by commas _(without spaces)_.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = self.arguments();
+
+        if args.is_empty() {
+            f.write_str(self.mnemonic())
+        } else {
+            write!(f, "{} {}", self.mnemonic(), args.join(","))
+        }
+    }
+}
+
+impl_mnemonic_for_instructions! {
     CreateVariable => VAR,
     Move => MOV,
     Increment => INC,
@@ -106,16 +375,52 @@ impl_display_for_instructions! {
     Multiply => MUL,
     Divide => DIV,
     Power => POW,
+    SquareRoot => SQRT,
+    Sine => SIN,
+    Cosine => COS,
+    Logarithm => LOG,
+    Floor => FLOOR,
+    Ceil => CEIL,
+    Round => ROUND,
     Compare => CMP,
     JumpEqual => JEQ,
     JumpNotEqual => JNE,
+    JumpLess => JLT,
+    JumpGreater => JGT,
+    JumpLessEqual => JLE,
+    JumpGreaterEqual => JGE,
     Jump => JMP,
+    Call => CALL,
+    Return => RET,
     ReadNumericValue => RNV,
     ReadStringValue => RSV,
     GenerateRandomNumber => RNG,
     Push => PSH,
     Pop => POP,
+    CreateList => LST,
+    GetItem => GET,
+    SetItem => SET,
+    InsertItem => INS,
+    RemoveItem => RMV,
+    CreateMap => MAP,
+    HasKey => HAS,
+    Keys => KEY,
+    Substring => SUBSTR,
+    Find => FIND,
+    Replace => REPLACE,
+    Uppercase => UPPER,
+    Lowercase => LOWER,
+    Trim => TRIM,
+    Reverse => REVERSE,
+    Repeat => REPEAT,
+    Split => SPLIT,
+    CharCode => ORD,
+    CharFromCode => CHR,
     Format => FMT,
+    IntoNumber => NUM,
+    IntoFloat => FLT,
+    IntoString => STR,
+    TypeOf => TYPEOF,
     Print => SAY,
     Length => LEN,
     Clear => CLR,
@@ -124,21 +429,76 @@ impl_display_for_instructions! {
     Die => DIE,
 }
 
-impl TryFrom<&str> for Instruction {
-    type Error = ParseError;
+impl Instruction {
+    /// Parses a single line of source code, keeping track of where each part of the instruction is located.
+    ///
+    /// Leading and trailing whitespace and comments (starting with `;` or `#`) are ignored.
+    /// Mnemonics are case-insensitive.
+    /// Returns `None` if the line doesn't contain any code.
+    ///
+    /// # Errors
+    /// Returns a [`ParseError`] pointing at the offending part of `line`.
+    pub fn parse_spanned(
+        line: &str,
+        file: Option<Rc<str>>,
+        line_no: usize,
+    ) -> Result<Option<SpannedInstruction>, ParseError> {
+        let (code, _comment) = ArgParserStateMachine::split_comment(line);
+        let indent = code.chars().take_while(|ch| ch.is_whitespace()).count();
+        let code = code.trim();
+        let code_len = code.chars().count();
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let (instr, maybe_args) = if value.len() == 3 {
-            (value, "")
+        if code.is_empty() {
+            return Ok(None);
+        }
+
+        let origin = Span::new(file, line_no, indent..indent + code_len);
+
+        let (instr, maybe_args) = if code_len == 3 {
+            (code, "")
         } else {
-            value
-                .split_once(' ')
-                .ok_or(Self::Error::MissingInstrArgsSep)?
+            code.split_once(char::is_whitespace)
+                .ok_or_else(|| ParseErrorKind::MissingInstrArgsSep.at(&origin))?
         };
 
-        let args: Arguments = ArgParserStateMachine::parse_args(maybe_args)?.into();
+        let instr_len = instr.chars().count();
+        let mnemonic = origin.with_columns(indent..indent + instr_len);
+        let args_start = indent + instr_len + 1;
+        let args_origin = origin.with_columns(args_start..args_start);
+        let args = Arguments::new(
+            ArgParserStateMachine::parse_args_spanned(maybe_args, &args_origin)?,
+            origin.with_columns(origin.columns.end..origin.columns.end),
+        );
+
+        let parsed = Self::from_parts(instr, &args, &mnemonic)?;
+
+        Ok(Some(SpannedInstruction {
+            instr: parsed,
+            span: InstrSpan {
+                mnemonic,
+                args: args.spans(),
+            },
+        }))
+    }
+
+    /// Returns the target of a jump instruction, or `None` for other instructions.
+    #[must_use]
+    pub const fn jump_target(&self) -> Option<&JumpTarget> {
+        match self {
+            Self::JumpEqual(target)
+            | Self::JumpNotEqual(target)
+            | Self::JumpLess(target)
+            | Self::JumpGreater(target)
+            | Self::JumpLessEqual(target)
+            | Self::JumpGreaterEqual(target)
+            | Self::Jump(target)
+            | Self::Call(target) => Some(target),
+            _ => None,
+        }
+    }
 
-        match instr {
+    fn from_parts(instr: &str, args: &Arguments, mnemonic: &Span) -> Result<Self, ParseError> {
+        match instr.to_ascii_uppercase().as_str() {
             VAR => {
                 args.check_count_exact(1)?;
                 let ident = args.fetch_nth_as_ident(0).into_parse_err()?;
@@ -205,6 +565,48 @@ impl TryFrom<&str> for Instruction {
 
                 Ok(Self::Power(var, amount))
             }
+            SQRT => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::SquareRoot(var))
+            }
+            SIN => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Sine(var))
+            }
+            COS => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Cosine(var))
+            }
+            LOG => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Logarithm(var))
+            }
+            FLOOR => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Floor(var))
+            }
+            CEIL => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Ceil(var))
+            }
+            ROUND => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Round(var))
+            }
             CMP => {
                 args.check_count_exact(2)?;
                 let var = args.fetch_nth_as_ident(0).into_parse_err()?;
@@ -214,21 +616,56 @@ impl TryFrom<&str> for Instruction {
             }
             JNE => {
                 args.check_count_exact(1)?;
-                let amount = args.fetch_nth_as_number(0).into_parse_err()?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
 
-                Ok(Self::JumpNotEqual(amount))
+                Ok(Self::JumpNotEqual(target))
             }
             JEQ => {
                 args.check_count_exact(1)?;
-                let amount = args.fetch_nth_as_number(0).into_parse_err()?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
+
+                Ok(Self::JumpEqual(target))
+            }
+            JLT => {
+                args.check_count_exact(1)?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
+
+                Ok(Self::JumpLess(target))
+            }
+            JGT => {
+                args.check_count_exact(1)?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
+
+                Ok(Self::JumpGreater(target))
+            }
+            JLE => {
+                args.check_count_exact(1)?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
 
-                Ok(Self::JumpEqual(amount))
+                Ok(Self::JumpLessEqual(target))
+            }
+            JGE => {
+                args.check_count_exact(1)?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
+
+                Ok(Self::JumpGreaterEqual(target))
             }
             JMP => {
                 args.check_count_exact(1)?;
-                let amount = args.fetch_nth_as_number(0).into_parse_err()?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
+
+                Ok(Self::Jump(target))
+            }
+            CALL => {
+                args.check_count_exact(1)?;
+                let target = args.fetch_nth_as_jump_target(0).into_parse_err()?;
+
+                Ok(Self::Call(target))
+            }
+            RET => {
+                args.check_count_exact(0)?;
 
-                Ok(Self::Jump(amount))
+                Ok(Self::Return)
             }
             RNV => {
                 args.check_count_exact(1)?;
@@ -274,12 +711,176 @@ impl TryFrom<&str> for Instruction {
 
                 Ok(Self::Pop(what, pop_where))
             }
+            LST => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::CreateList(var))
+            }
+            GET => {
+                args.check_count_exact(3)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let list = args.fetch_nth_as_ident(1).into_parse_err()?;
+                let index = args.fetch_nth_as_any(2).into_parse_err()?;
+
+                Ok(Self::GetItem(dst, list, index))
+            }
+            SET => {
+                args.check_count_exact(3)?;
+                let list = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let index = args.fetch_nth_as_any(1).into_parse_err()?;
+                let value = args.fetch_nth_as_any(2).into_parse_err()?;
+
+                Ok(Self::SetItem(list, index, value))
+            }
+            INS => {
+                args.check_count_exact(3)?;
+                let list = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let index = args.fetch_nth_as_any(1).into_parse_err()?;
+                let value = args.fetch_nth_as_any(2).into_parse_err()?;
+
+                Ok(Self::InsertItem(list, index, value))
+            }
+            RMV => {
+                args.check_count(2, 3)?;
+                let list = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let index = args.fetch_nth_as_any(1).into_parse_err()?;
+                let removed_where = args.fetch_nth_as_ident(2).into_optional()?;
+
+                Ok(Self::RemoveItem(list, index, removed_where))
+            }
+            MAP => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::CreateMap(var))
+            }
+            HAS => {
+                args.check_count_exact(2)?;
+                let map = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let key = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::HasKey(map, key))
+            }
+            KEY => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let map = args.fetch_nth_as_ident(1).into_parse_err()?;
+
+                Ok(Self::Keys(dst, map))
+            }
+            NUM => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::IntoNumber(dst, src))
+            }
+            FLT => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::IntoFloat(dst, src))
+            }
+            STR => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::IntoString(dst, src))
+            }
+            TYPEOF => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::TypeOf(dst, src))
+            }
+            SUBSTR => {
+                args.check_count(3, 4)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+                let start = args.fetch_nth_as_any(2).into_parse_err()?;
+                let end = args.fetch_nth_as_any(3).into_optional()?;
+
+                Ok(Self::Substring(dst, src, start, end))
+            }
+            FIND => {
+                args.check_count_exact(3)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+                let needle = args.fetch_nth_as_any(2).into_parse_err()?;
+
+                Ok(Self::Find(dst, src, needle))
+            }
+            REPLACE => {
+                args.check_count_exact(3)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let from = args.fetch_nth_as_any(1).into_parse_err()?;
+                let to = args.fetch_nth_as_any(2).into_parse_err()?;
+
+                Ok(Self::Replace(var, from, to))
+            }
+            UPPER => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Uppercase(var))
+            }
+            LOWER => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Lowercase(var))
+            }
+            TRIM => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Trim(var))
+            }
+            REVERSE => {
+                args.check_count_exact(1)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+
+                Ok(Self::Reverse(var))
+            }
+            REPEAT => {
+                args.check_count_exact(2)?;
+                let var = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let count = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::Repeat(var, count))
+            }
+            SPLIT => {
+                args.check_count_exact(3)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+                let separator = args.fetch_nth_as_any(2).into_parse_err()?;
+
+                Ok(Self::Split(dst, src, separator))
+            }
+            ORD => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let src = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::CharCode(dst, src))
+            }
+            CHR => {
+                args.check_count_exact(2)?;
+                let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
+                let code = args.fetch_nth_as_any(1).into_parse_err()?;
+
+                Ok(Self::CharFromCode(dst, code))
+            }
             FMT => {
                 args.check_count_exact(2)?;
                 let dst = args.fetch_nth_as_ident(0).into_parse_err()?;
-                let fmt_text = args.fetch_nth::<Text>(1)?;
+                let template = args.fetch_nth_as_template(1)?;
 
-                Ok(Self::Format(dst, fmt_text))
+                Ok(Self::Format(dst, template))
             }
             SAY => {
                 args.check_count_exact(1)?;
@@ -318,7 +919,225 @@ impl TryFrom<&str> for Instruction {
 
                 Ok(Self::Die(expr.unwrap_or_default()))
             }
-            other => Err(Self::Error::IllegalInstruction(other.into())),
+            _ => Err(ParseErrorKind::IllegalInstruction(instr.into()).at(mnemonic)),
+        }
+    }
+}
+
+impl TryFrom<&str> for Instruction {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match Self::parse_spanned(value, None, 1)? {
+            Some(spanned) => Ok(spanned.instr),
+            None => Err(ParseErrorKind::MissingInstruction.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastrand::Rng;
+
+    /// Characters used in generated strings, including ones which must be escaped.
+    const STRING_CHARS: &[char] = &[
+        'a', 'Z', '0', ' ', ',', ';', '#', '{', '}', '"', '\'', '\\', '\n', '\t', '\u{1}', 'ü',
+        '😀',
+    ];
+    const FORMAT_SPECS: &[&str] = &["", ">5", "*^08.3x", "<3", "X", "b", ".2", "0>4"];
+
+    /// Generates random instructions with random arguments.
+    struct Gen(Rng);
+
+    impl Gen {
+        fn ident(&mut self) -> Identifier {
+            loop {
+                let len = self.0.usize(1..8);
+                let name: String = (0..len)
+                    .map(|_| match self.0.u8(..10) {
+                        0 => '_',
+                        _ => self.0.alphabetic(),
+                    })
+                    .collect();
+
+                if name != "NaN" {
+                    return Identifier::new(name);
+                }
+            }
         }
+
+        fn text(&mut self) -> String {
+            let len = self.0.usize(..10);
+            (0..len)
+                .map(|_| STRING_CHARS[self.0.usize(..STRING_CHARS.len())])
+                .collect()
+        }
+
+        fn float(&mut self) -> f32 {
+            match self.0.u8(..4) {
+                0 => f32::from_bits(self.0.u32(..)),
+                1 => [f32::INFINITY, f32::NEG_INFINITY, f32::NAN][self.0.usize(..3)],
+                _ => self.0.f32() * 100.0 - 50.0,
+            }
+        }
+
+        fn expr(&mut self) -> Expression {
+            match self.0.u8(..4) {
+                0 => Expression::Identifier(self.ident()),
+                1 => Expression::Number(self.0.i64(..)),
+                2 => Expression::Float(self.float()),
+                _ => Expression::make_string(self.text()),
+            }
+        }
+
+        fn target(&mut self) -> JumpTarget {
+            match self.0.bool() {
+                true => JumpTarget::Offset(self.0.i64(..)),
+                false => JumpTarget::Label(self.ident()),
+            }
+        }
+
+        fn template(&mut self) -> Template {
+            let mut text = String::new();
+
+            for _ in 0..self.0.usize(..5) {
+                match self.0.u8(..3) {
+                    0 => {
+                        let spec = FORMAT_SPECS[self.0.usize(..FORMAT_SPECS.len())];
+                        let separator = if spec.is_empty() { "" } else { ":" };
+                        text.push_str(&format!("{{{}{separator}{spec}}}", self.ident()));
+                    }
+                    1 => text.push_str(["{{", "}}"][self.0.usize(..2)]),
+                    _ => text.push_str(&self.text().replace(['{', '}'], "")),
+                }
+            }
+
+            Template::parse(&text).unwrap()
+        }
+
+        fn maybe<T>(&mut self, generate: impl FnOnce(&mut Self) -> T) -> Option<T> {
+            self.0.bool().then(|| generate(self))
+        }
+
+        /// Generates an instruction with the given mnemonic.
+        fn instruction(&mut self, mnemonic: &str) -> Instruction {
+            use Instruction as I;
+
+            match mnemonic {
+                VAR => I::CreateVariable(self.ident()),
+                MOV => I::Move(self.ident(), self.expr()),
+                INC => I::Increment(self.ident()),
+                DEC => I::Decrement(self.ident()),
+                DMP => I::Dump(self.expr()),
+                ADD => I::Add(self.ident(), self.expr()),
+                SUB => I::Subtract(self.ident(), self.expr()),
+                MUL => I::Multiply(self.ident(), self.expr()),
+                DIV => I::Divide(self.ident(), self.expr()),
+                POW => I::Power(self.ident(), self.expr()),
+                SQRT => I::SquareRoot(self.ident()),
+                SIN => I::Sine(self.ident()),
+                COS => I::Cosine(self.ident()),
+                LOG => I::Logarithm(self.ident()),
+                FLOOR => I::Floor(self.ident()),
+                CEIL => I::Ceil(self.ident()),
+                ROUND => I::Round(self.ident()),
+                CMP => I::Compare(self.ident(), self.expr()),
+                JNE => I::JumpNotEqual(self.target()),
+                JEQ => I::JumpEqual(self.target()),
+                JLT => I::JumpLess(self.target()),
+                JGT => I::JumpGreater(self.target()),
+                JLE => I::JumpLessEqual(self.target()),
+                JGE => I::JumpGreaterEqual(self.target()),
+                JMP => I::Jump(self.target()),
+                CALL => I::Call(self.target()),
+                RET => I::Return,
+                RNV => I::ReadNumericValue(self.ident()),
+                RSV => I::ReadStringValue(self.ident()),
+                RNG => match self.0.bool() {
+                    true => {
+                        I::GenerateRandomNumber(self.ident(), Some(self.expr()), Some(self.expr()))
+                    }
+                    false => I::GenerateRandomNumber(self.ident(), None, None),
+                },
+                PSH => I::Push(self.ident(), self.expr()),
+                POP => I::Pop(self.ident(), self.maybe(Self::ident)),
+                LST => I::CreateList(self.ident()),
+                GET => I::GetItem(self.ident(), self.ident(), self.expr()),
+                SET => I::SetItem(self.ident(), self.expr(), self.expr()),
+                INS => I::InsertItem(self.ident(), self.expr(), self.expr()),
+                RMV => I::RemoveItem(self.ident(), self.expr(), self.maybe(Self::ident)),
+                MAP => I::CreateMap(self.ident()),
+                HAS => I::HasKey(self.ident(), self.expr()),
+                KEY => I::Keys(self.ident(), self.ident()),
+                NUM => I::IntoNumber(self.ident(), self.expr()),
+                FLT => I::IntoFloat(self.ident(), self.expr()),
+                STR => I::IntoString(self.ident(), self.expr()),
+                TYPEOF => I::TypeOf(self.ident(), self.expr()),
+                SUBSTR => I::Substring(
+                    self.ident(),
+                    self.expr(),
+                    self.expr(),
+                    self.maybe(Self::expr),
+                ),
+                FIND => I::Find(self.ident(), self.expr(), self.expr()),
+                REPLACE => I::Replace(self.ident(), self.expr(), self.expr()),
+                UPPER => I::Uppercase(self.ident()),
+                LOWER => I::Lowercase(self.ident()),
+                TRIM => I::Trim(self.ident()),
+                REVERSE => I::Reverse(self.ident()),
+                REPEAT => I::Repeat(self.ident(), self.expr()),
+                SPLIT => I::Split(self.ident(), self.expr(), self.expr()),
+                ORD => I::CharCode(self.ident(), self.expr()),
+                CHR => I::CharFromCode(self.ident(), self.expr()),
+                FMT => I::Format(self.ident(), self.template()),
+                SAY => I::Print(self.expr()),
+                LEN => I::Length(self.ident(), self.expr()),
+                CLR => I::Clear(self.ident()),
+                HLT => I::Sleep(self.expr()),
+                DEL => I::Delete(self.ident()),
+                DIE => I::Die(self.maybe(|gen| gen.0.i64(..)).unwrap_or_default()),
+                other => unreachable!("no generator for `{other}`"),
+            }
+        }
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let mut gen = Gen(Rng::with_seed(0x5A5A));
+
+        for _ in 0..200 {
+            for &mnemonic in ALL {
+                let instr = gen.instruction(mnemonic);
+                let source = instr.to_string();
+                let parsed = Instruction::try_from(source.as_str())
+                    .unwrap_or_else(|why| panic!("`{source}` failed to parse: {why}"));
+
+                assert_eq!(instr.mnemonic(), mnemonic);
+                // `NaN != NaN`, but its `Debug` output compares equal
+                assert_eq!(format!("{parsed:?}"), format!("{instr:?}"), "`{source}`");
+            }
+        }
+    }
+
+    #[test]
+    fn display_source() {
+        for (source, expected) in [
+            ("ret", "RET"),
+            ("DIE 0", "DIE"),
+            ("mov  x, 'it\\'s'", "MOV x,\"it's\""),
+            ("SAY \"a\\tb\\u{1}\"", "SAY \"a\\tb\\u{1}\""),
+            ("RNG x , 1 , 2.50", "RNG x,1,2.5"),
+            ("MOV x,1e39", "MOV x,1e39"),
+            ("fmt s,'{{{x:>4}}} \"q\"'", "FMT s,\"{{{x:>4}}} \\\"q\\\"\""),
+            ("jmp -2", "JMP -2"),
+            ("substr a,b,1", "SUBSTR a,b,1"),
+            ("MOV inf,nan", "MOV inf,nan"),
+            ("MOV x,NaN", "MOV x,NaN"),
+        ] {
+            assert_eq!(Instruction::try_from(source).unwrap().to_string(), expected);
+        }
+
+        assert!(Instruction::try_from("VAR NaN").is_err());
     }
 }
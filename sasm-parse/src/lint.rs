@@ -0,0 +1,435 @@
+use crate::{expression::Expression, ident::Identifier, program::Program, span::Span, Instruction};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+};
+use thiserror::Error;
+
+/// A likely mistake found by [`lint()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub kind: LintKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LintKind {
+    #[error("Variable '{0}' is never declared")]
+    UndeclaredVariable(String),
+    #[error("Variable '{0}' is used before VAR")]
+    UseBeforeDeclaration(String),
+    #[error("Variable '{0}' is used after it was deleted")]
+    UseAfterDelete(String),
+    #[error("Variable '{0}' is never read")]
+    UnusedVariable(String),
+    #[error("Variable '{0}' is internal and read-only")]
+    InternalWrite(String),
+    #[error("Instruction jumps to itself")]
+    SelfJump,
+    #[error("Unreachable instruction")]
+    Unreachable,
+    #[error("{0} without a preceding CMP")]
+    MissingCompare(&'static str),
+}
+
+/// How serious a [`Lint`] is. Errors will fail when executed, warnings might be intentional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// How an instruction uses a variable.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Declare,
+    Read,
+    Write,
+    /// Reads and writes the variable (eg. `INC`).
+    Update,
+    Delete,
+}
+
+impl LintKind {
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        match self {
+            Self::UndeclaredVariable(..) | Self::InternalWrite(..) | Self::SelfJump => {
+                Severity::Error
+            }
+            Self::UseBeforeDeclaration(..)
+            | Self::UseAfterDelete(..)
+            | Self::UnusedVariable(..)
+            | Self::Unreachable
+            | Self::MissingCompare(..) => Severity::Warning,
+        }
+    }
+}
+
+impl Lint {
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => f.write_str("Warning"),
+            Self::Error => f.write_str("Error"),
+        }
+    }
+}
+
+/// Looks for likely mistakes in a program, without executing it.
+///
+/// Variables are tracked in the order of the source code, so code reached only through jumps
+/// may cause false warnings.
+///
+/// ```rust
+/// # use sasm_parse::{lint::{lint, LintKind, Severity}, program::Program};
+/// let program = Program::parse("VAR x\nMOV x,y\nDIE 0\nDMP x").unwrap();
+/// let lints = lint(&program);
+///
+/// assert_eq!(lints[0].kind, LintKind::UndeclaredVariable("y".into()));
+/// assert_eq!(lints[0].severity(), Severity::Error);
+/// assert_eq!(lints[1].kind, LintKind::Unreachable);
+/// ```
+#[must_use]
+pub fn lint(program: &Program) -> Vec<Lint> {
+    let mut lints = check_variables(program);
+    lints.extend(check_control_flow(program));
+    lints.sort_by_key(|lint| (lint.span.line, lint.span.columns.start));
+
+    lints
+}
+
+fn check_variables(program: &Program) -> Vec<Lint> {
+    // Assigning a value also creates a variable
+    let all_declared: HashSet<_> = program
+        .instructions()
+        .iter()
+        .flat_map(|instr| accesses(&instr.instr))
+        .filter(|(_, _, access)| matches!(access, Access::Declare | Access::Write))
+        .map(|(ident, ..)| ident)
+        .collect();
+
+    let mut lints = Vec::new();
+    let mut declarations = HashMap::new();
+    let mut declared = HashSet::new();
+    let mut deleted = HashSet::new();
+    let mut reported = HashSet::new();
+    let mut read = HashSet::new();
+
+    for instr in program.instructions() {
+        for (ident, n, access) in accesses(&instr.instr) {
+            let span = instr.span.arg_or_full(n);
+            let mut report = |kind| {
+                lints.push(Lint {
+                    kind,
+                    span: span.clone(),
+                })
+            };
+
+            if ident.is_internal() {
+                if access != Access::Read {
+                    report(LintKind::InternalWrite(ident.to_string()));
+                }
+                continue;
+            }
+
+            if matches!(access, Access::Read | Access::Update) {
+                read.insert(ident);
+            }
+
+            // Assigning a value to a variable which doesn't exist declares it
+            let declares =
+                access == Access::Declare || (access == Access::Write && !declared.contains(ident));
+
+            if declares {
+                declarations.entry(ident).or_insert_with(|| span.clone());
+                declared.insert(ident);
+                deleted.remove(ident);
+                continue;
+            }
+
+            if deleted.contains(ident) && access != Access::Write {
+                report(LintKind::UseAfterDelete(ident.to_string()));
+            } else if !declared.contains(ident) && reported.insert(ident) {
+                report(if all_declared.contains(ident) {
+                    LintKind::UseBeforeDeclaration(ident.to_string())
+                } else {
+                    LintKind::UndeclaredVariable(ident.to_string())
+                });
+            }
+
+            match access {
+                Access::Write => {
+                    declared.insert(ident);
+                    deleted.remove(ident);
+                }
+                Access::Delete => {
+                    deleted.insert(ident);
+                }
+                _ => {
+                    deleted.remove(ident);
+                }
+            }
+        }
+    }
+
+    for (ident, span) in declarations {
+        if !read.contains(ident) {
+            lints.push(Lint {
+                kind: LintKind::UnusedVariable(ident.to_string()),
+                span,
+            });
+        }
+    }
+
+    lints
+}
+
+fn check_control_flow(program: &Program) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut destinations = HashSet::new();
+    let mut compared = false;
+
+    for (pos, instr) in program.instructions().iter().enumerate() {
+        compared |= matches!(
+            instr.instr,
+            Instruction::Compare(..) | Instruction::HasKey(..)
+        );
+
+        let Some(target) = instr.instr.jump_target() else {
+            continue;
+        };
+        let conditional = !matches!(instr.instr, Instruction::Jump(..) | Instruction::Call(..));
+
+        if !compared && conditional {
+            lints.push(Lint {
+                kind: LintKind::MissingCompare(instr.instr.mnemonic()),
+                span: instr.span.mnemonic.clone(),
+            });
+        }
+
+        // Jumps outside of the program are already rejected by the parser
+        let Some(dest) = program.jump_destination(pos, target) else {
+            continue;
+        };
+
+        destinations.insert(dest);
+
+        if dest == pos {
+            lints.push(Lint {
+                kind: LintKind::SelfJump,
+                span: instr.span.arg_or_full(0),
+            });
+        }
+    }
+
+    let instructions = program.instructions();
+
+    for (pos, pair) in instructions.windows(2).enumerate() {
+        let ends_flow = matches!(
+            pair[0].instr,
+            Instruction::Die(..) | Instruction::Jump(..) | Instruction::Return
+        );
+
+        if ends_flow && !destinations.contains(&(pos + 1)) {
+            lints.push(Lint {
+                kind: LintKind::Unreachable,
+                span: pair[1].span.full(),
+            });
+        }
+    }
+
+    lints
+}
+
+/// Returns every variable used by an instruction, together with the index of the argument.
+fn accesses(instr: &Instruction) -> Vec<(&Identifier, usize, Access)> {
+    let mut result = Vec::new();
+
+    match instr {
+        Instruction::CreateVariable(ident) => result.push((ident, 0, Access::Declare)),
+        Instruction::Delete(ident) => result.push((ident, 0, Access::Delete)),
+        Instruction::Move(dst, src)
+        | Instruction::IntoNumber(dst, src)
+        | Instruction::IntoFloat(dst, src)
+        | Instruction::IntoString(dst, src)
+        | Instruction::TypeOf(dst, src)
+        | Instruction::CharCode(dst, src)
+        | Instruction::CharFromCode(dst, src)
+        | Instruction::Length(dst, src) => {
+            result.push((dst, 0, Access::Write));
+            result.extend(expr_reads([Some(src)], 1));
+        }
+        Instruction::Increment(ident)
+        | Instruction::Decrement(ident)
+        | Instruction::SquareRoot(ident)
+        | Instruction::Sine(ident)
+        | Instruction::Cosine(ident)
+        | Instruction::Logarithm(ident)
+        | Instruction::Floor(ident)
+        | Instruction::Ceil(ident)
+        | Instruction::Round(ident)
+        | Instruction::Uppercase(ident)
+        | Instruction::Lowercase(ident)
+        | Instruction::Trim(ident)
+        | Instruction::Reverse(ident) => result.push((ident, 0, Access::Update)),
+        Instruction::Add(ident, expr)
+        | Instruction::Subtract(ident, expr)
+        | Instruction::Multiply(ident, expr)
+        | Instruction::Divide(ident, expr)
+        | Instruction::Power(ident, expr)
+        | Instruction::Push(ident, expr)
+        | Instruction::Repeat(ident, expr) => {
+            result.push((ident, 0, Access::Update));
+            result.extend(expr_reads([Some(expr)], 1));
+        }
+        Instruction::SetItem(ident, first, second)
+        | Instruction::InsertItem(ident, first, second)
+        | Instruction::Replace(ident, first, second) => {
+            result.push((ident, 0, Access::Update));
+            result.extend(expr_reads([Some(first), Some(second)], 1));
+        }
+        Instruction::Compare(ident, expr) | Instruction::HasKey(ident, expr) => {
+            result.push((ident, 0, Access::Read));
+            result.extend(expr_reads([Some(expr)], 1));
+        }
+        Instruction::Dump(expr) | Instruction::Print(expr) | Instruction::Sleep(expr) => {
+            result.extend(expr_reads([Some(expr)], 0));
+        }
+        Instruction::ReadNumericValue(dst)
+        | Instruction::ReadStringValue(dst)
+        | Instruction::CreateList(dst)
+        | Instruction::CreateMap(dst)
+        | Instruction::Clear(dst) => result.push((dst, 0, Access::Write)),
+        Instruction::GenerateRandomNumber(dst, min, max) => {
+            result.push((dst, 0, Access::Write));
+            result.extend(expr_reads([min.as_ref(), max.as_ref()], 1));
+        }
+        Instruction::Pop(ident, dst) => {
+            result.push((ident, 0, Access::Update));
+
+            if let Some(dst) = dst {
+                result.push((dst, 1, Access::Write));
+            }
+        }
+        Instruction::GetItem(dst, container, key) => {
+            result.push((dst, 0, Access::Write));
+            result.push((container, 1, Access::Read));
+            result.extend(expr_reads([Some(key)], 2));
+        }
+        Instruction::RemoveItem(container, key, dst) => {
+            result.push((container, 0, Access::Update));
+            result.extend(expr_reads([Some(key)], 1));
+
+            if let Some(dst) = dst {
+                result.push((dst, 2, Access::Write));
+            }
+        }
+        Instruction::Keys(dst, map) => {
+            result.push((dst, 0, Access::Write));
+            result.push((map, 1, Access::Read));
+        }
+        Instruction::Substring(dst, src, start, end) => {
+            result.push((dst, 0, Access::Write));
+            result.extend(expr_reads([Some(src), Some(start), end.as_ref()], 1));
+        }
+        Instruction::Find(dst, src, other) | Instruction::Split(dst, src, other) => {
+            result.push((dst, 0, Access::Write));
+            result.extend(expr_reads([Some(src), Some(other)], 1));
+        }
+        Instruction::Format(dst, template) => {
+            result.push((dst, 0, Access::Write));
+            result.extend(template.variables().map(|ident| (ident, 1, Access::Read)));
+        }
+        Instruction::JumpEqual(..)
+        | Instruction::JumpNotEqual(..)
+        | Instruction::JumpLess(..)
+        | Instruction::JumpGreater(..)
+        | Instruction::JumpLessEqual(..)
+        | Instruction::JumpGreaterEqual(..)
+        | Instruction::Jump(..)
+        | Instruction::Call(..)
+        | Instruction::Return
+        | Instruction::Die(..) => (),
+    }
+
+    result
+}
+
+/// Returns the variables read by consecutive arguments, starting at argument `first`.
+fn expr_reads<const N: usize>(
+    exprs: [Option<&Expression>; N],
+    first: usize,
+) -> impl Iterator<Item = (&Identifier, usize, Access)> {
+    exprs
+        .into_iter()
+        .enumerate()
+        .filter_map(move |(n, expr)| match expr? {
+            Expression::Identifier(ident) => Some((ident, first + n, Access::Read)),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, LintKind};
+    use crate::program::Program;
+
+    fn lints(source: &str) -> Vec<(usize, LintKind)> {
+        lint(&Program::parse(source).unwrap())
+            .into_iter()
+            .map(|lint| (lint.span.line, lint.kind))
+            .collect()
+    }
+
+    #[test]
+    fn clean_program() {
+        let source =
+            "JMP start\nshow:\nDMP x\nRET\nstart:\nVAR x\nRNV x\nCMP x,0\nJEQ end\nCALL show\nend:";
+
+        assert_eq!(
+            lints(source),
+            vec![(3, LintKind::UseBeforeDeclaration("x".into()))]
+        );
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(
+            lints("VAR x\nVAR y\nMOV y,z\nMOV _PI,1\nDEL x\nDMP x\nVAR x\nDMP x"),
+            vec![
+                (2, LintKind::UnusedVariable("y".into())),
+                (3, LintKind::UndeclaredVariable("z".into())),
+                (4, LintKind::InternalWrite("_PI".into())),
+                (6, LintKind::UseAfterDelete("x".into())),
+            ]
+        );
+        // Assigning a value creates a variable, even after `DEL`
+        assert_eq!(lints("RNV n\nDMP n\nDEL n\nMOV n,1\nDMP n"), vec![]);
+        assert_eq!(
+            lints("DMP n\nMOV n,1\nRNV m"),
+            vec![
+                (1, LintKind::UseBeforeDeclaration("n".into())),
+                (3, LintKind::UnusedVariable("m".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        assert_eq!(
+            lints("JNE 2\nJMP 4\nDIE 0\nloop:\nJMP loop\nDIE 1"),
+            vec![
+                (1, LintKind::MissingCompare("JNE")),
+                (5, LintKind::SelfJump),
+                (6, LintKind::Unreachable),
+            ]
+        );
+    }
+}
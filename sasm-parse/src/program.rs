@@ -0,0 +1,261 @@
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    expression::Number,
+    label::{JumpTarget, LabelTable, Line},
+    SpannedInstruction,
+};
+use std::rc::Rc;
+
+/// A whole parsed SASM script.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+    file: Option<Rc<str>>,
+    source: Vec<String>,
+    instructions: Vec<SpannedInstruction>,
+    labels: LabelTable,
+}
+
+impl Program {
+    /// Parses a whole script.
+    ///
+    /// ```rust
+    /// # use sasm_parse::program::Program;
+    /// let program = Program::parse("VAR x\n\nloop:\nINC x ; forever\nJMP loop").unwrap();
+    ///
+    /// assert_eq!(program.len(), 3);
+    /// assert_eq!(program.instructions()[1].span.line(), 4);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns every error found in the script, ordered by line.
+    pub fn parse(source: &str) -> Result<Self, Vec<ParseError>> {
+        Self::parse_source(source, None)
+    }
+
+    /// Parses a whole script read from `file`. The file name is included in the spans of
+    /// instructions and errors.
+    ///
+    /// # Errors
+    /// Returns every error found in the script, ordered by line.
+    pub fn parse_source(source: &str, file: Option<&str>) -> Result<Self, Vec<ParseError>> {
+        let file: Option<Rc<str>> = file.map(Rc::from);
+        let mut errors = Vec::new();
+        // Lines which failed to parse are kept as `Err(label)`, so they still take up their
+        // place when labels and jump offsets are checked
+        let lines: Vec<_> = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                Line::parse(line, file.clone(), i + 1).map_err(|why| {
+                    errors.push(why);
+                    Line::is_label(line)
+                })
+            })
+            .collect();
+        let line_refs: Vec<_> = lines
+            .iter()
+            .map(|line| line.as_ref().map_err(|label| *label))
+            .collect();
+
+        let labels = LabelTable::resolve_partial(&line_refs).unwrap_or_else(|label_errors| {
+            errors.extend(label_errors);
+            LabelTable::default()
+        });
+        errors.extend(check_jump_offsets(&line_refs));
+
+        let instructions: Vec<_> = lines
+            .into_iter()
+            .filter_map(|line| match line {
+                Ok(Line::Instruction(instr)) => Some(instr),
+                _ => None,
+            })
+            .collect();
+
+        let program = Self {
+            file,
+            source: source.lines().map(String::from).collect(),
+            instructions,
+            labels,
+        };
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|error| error.span.as_ref().map(|span| span.line));
+            return Err(errors);
+        }
+
+        Ok(program)
+    }
+
+    /// Returns the index of the instruction a jump at `pos` continues at, or `None` if the
+    /// target is outside of the program or an undefined label.
+    ///
+    /// Offsets are relative to the jump itself. A jump right after the last instruction is
+    /// allowed and ends the program.
+    ///
+    /// ```rust
+    /// # use sasm_parse::{label::JumpTarget, program::Program};
+    /// let program = Program::parse("DMP 1\nDMP 2").unwrap();
+    ///
+    /// assert_eq!(program.jump_destination(1, &JumpTarget::Offset(-1)), Some(0));
+    /// assert_eq!(program.jump_destination(1, &JumpTarget::Offset(1)), Some(2));
+    /// assert_eq!(program.jump_destination(1, &JumpTarget::Offset(2)), None);
+    /// ```
+    #[must_use]
+    pub fn jump_destination(&self, pos: usize, target: &JumpTarget) -> Option<usize> {
+        match target {
+            JumpTarget::Offset(offset) => offset_destination(pos, *offset, self.len()),
+            JumpTarget::Label(name) => self.labels.get(name),
+        }
+    }
+
+    /// Returns the name of the file the script was read from, if any.
+    #[must_use]
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns all instructions, in order of execution.
+    #[must_use]
+    pub fn instructions(&self) -> &[SpannedInstruction] {
+        &self.instructions
+    }
+
+    /// Returns the instruction at the given index.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&SpannedInstruction> {
+        self.instructions.get(index)
+    }
+
+    /// Returns the number of instructions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Returns `true` if the program contains no instructions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /// Returns the label definitions.
+    #[must_use]
+    pub const fn labels(&self) -> &LabelTable {
+        &self.labels
+    }
+
+    /// Returns all lines of the source code.
+    #[must_use]
+    pub fn source_lines(&self) -> &[String] {
+        &self.source
+    }
+
+    /// Returns the source code on the given physical line _(1-based)_.
+    #[must_use]
+    pub fn source_line(&self, line: usize) -> Option<&str> {
+        self.source.get(line.checked_sub(1)?).map(String::as_str)
+    }
+}
+
+fn offset_destination(pos: usize, offset: Number, len: usize) -> Option<usize> {
+    pos.checked_add_signed(isize::try_from(offset).ok()?)
+        .filter(|dest| *dest <= len)
+}
+
+/// Reports every jump whose offset points outside of the program. Lines which failed to parse
+/// are passed as `Err(label)`, invalid instructions are counted like valid ones.
+fn check_jump_offsets(lines: &[Result<&Line, bool>]) -> Vec<ParseError> {
+    let instructions: Vec<_> = lines
+        .iter()
+        .filter_map(|line| match line {
+            Ok(Line::Instruction(instr)) => Some(Some(instr)),
+            Err(false) => Some(None),
+            _ => None,
+        })
+        .collect();
+
+    instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(pos, instr)| {
+            let instr = (*instr)?;
+
+            match instr.instr.jump_target()? {
+                JumpTarget::Offset(offset)
+                    if offset_destination(pos, *offset, instructions.len()).is_none() =>
+                {
+                    Some(ParseErrorKind::JumpOutOfBounds(*offset).at(&instr.span.arg_or_full(0)))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Program;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn collects_all_errors() {
+        let errors = Program::parse("VAR x\nFOO x\n\nMOV x\nJMP nowhere").unwrap_err();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|error| error.span.as_ref().unwrap().line)
+            .collect();
+
+        assert_eq!(lines, vec![2, 4, 5]);
+        assert!(matches!(errors[2].kind, ParseErrorKind::UndefinedLabel(..)));
+    }
+
+    #[test]
+    fn syntax_errors_keep_positions() {
+        // The broken instruction still counts, so `JMP -2` stays inside the program
+        let errors = Program::parse("DMP 1\nDMP 1 2\nJMP -2\nJMP 3").unwrap_err();
+        let kinds: Vec<_> = errors.iter().map(|error| &error.kind).collect();
+
+        assert_eq!(kinds.len(), 2);
+        assert!(matches!(kinds[1], ParseErrorKind::JumpOutOfBounds(3)));
+
+        let errors = Program::parse("a:\nFOO\na:\nJMP a").unwrap_err();
+        assert!(matches!(errors[1].kind, ParseErrorKind::DuplicateLabel(..)));
+    }
+
+    #[test]
+    fn invalid_label_skips_undefined_labels() {
+        // `loop` might be the label which failed to parse
+        let errors = Program::parse("JMP loop\nloop x:").unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert!(!matches!(
+            errors[0].kind,
+            ParseErrorKind::UndefinedLabel(..)
+        ));
+    }
+
+    #[test]
+    fn checks_jump_offsets() {
+        let errors = Program::parse("JMP 5\nDMP 1\nJEQ -3\nJNE 1").unwrap_err();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|error| error.span.as_ref().unwrap().line)
+            .collect();
+
+        assert_eq!(lines, vec![1, 3]);
+        assert!(matches!(
+            errors[1].kind,
+            ParseErrorKind::JumpOutOfBounds(-3)
+        ));
+    }
+
+    #[test]
+    fn keeps_file_name_and_source() {
+        let program = Program::parse_source("; hello\nDMP 1", Some("hello.sasm")).unwrap();
+        let span = &program.instructions()[0].span.mnemonic;
+
+        assert_eq!(program.file(), Some("hello.sasm"));
+        assert_eq!(span.file.as_deref(), Some("hello.sasm"));
+        assert_eq!(program.source_line(span.line), Some("DMP 1"));
+    }
+}
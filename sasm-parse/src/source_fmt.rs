@@ -0,0 +1,99 @@
+use crate::{args_sm::ArgParserStateMachine, error::ParseError, label::Line};
+use std::rc::Rc;
+
+/// Formats a script into its canonical form.
+///
+/// - Instructions are written like [`Instruction`](crate::Instruction)'s `Display` does: uppercase
+///   mnemonics, arguments separated by commas without spaces and strings in double quotes.
+/// - Indentation and trailing whitespace is removed.
+/// - Comments are kept, a comment after code is separated from it by a single space.
+/// - Consecutive blank lines are merged into one, blank lines at the start and end are removed.
+/// - The result ends with a newline _(unless it's empty)_.
+///
+/// Formatting doesn't change the meaning of a script, and formatting a formatted script doesn't change it.
+///
+/// ```rust
+/// # use sasm_parse::source_fmt::format_source;
+/// let formatted = format_source("  mov x, 'hi'   ;greet\n\n\nloop:\nDIE", None).unwrap();
+///
+/// assert_eq!(formatted, "MOV x,\"hi\" ;greet\n\nloop:\nDIE\n");
+/// ```
+///
+/// # Errors
+/// Returns every syntax error in the script, ordered by line.
+pub fn format_source(source: &str, file: Option<&str>) -> Result<String, Vec<ParseError>> {
+    let file: Option<Rc<str>> = file.map(Rc::from);
+    let mut errors = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let code = match Line::parse(line, file.clone(), i + 1) {
+            Ok(Line::Blank) => String::new(),
+            Ok(Line::Label(label)) => format!("{}:", label.name),
+            Ok(Line::Instruction(instr)) => instr.instr.to_string(),
+            Err(why) => {
+                errors.push(why);
+                continue;
+            }
+        };
+        let formatted = match comment(line) {
+            Some(comment) if code.is_empty() => comment,
+            Some(comment) => format!("{code} {comment}"),
+            None => code,
+        };
+
+        let after_blank = lines.last().is_none_or(String::is_empty);
+        if !(formatted.is_empty() && after_blank) {
+            lines.push(formatted);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    if lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+
+    Ok(lines.into_iter().map(|line| line + "\n").collect())
+}
+
+/// Returns the comment of a line _(including the comment character)_ without trailing whitespace.
+fn comment(line: &str) -> Option<String> {
+    let (code, _) = ArgParserStateMachine::split_comment(line);
+    let comment = line[code.len()..].trim_end();
+
+    (!comment.is_empty()).then(|| comment.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_source;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn format_script() {
+        let source = "\n\n# header\nvar x\n  Mov x , 1   \n\tsay 'it'\nsay 'a\\'b'\n\n\n\nloop: # again\n    jmp   loop;forever\n\n";
+        let expected = "# header\nVAR x\nMOV x,1\nSAY \"it\"\nSAY \"a'b\"\n\nloop: # again\nJMP loop ;forever\n";
+
+        assert_eq!(format_source(source, None).unwrap(), expected);
+        assert_eq!(format_source(expected, None).unwrap(), expected);
+        assert_eq!(format_source("\n; \n", None).unwrap(), ";\n");
+    }
+
+    #[test]
+    fn format_errors() {
+        let errors = format_source("MOV x,1\nFOO 1\nMOV x y", Some("test.sasm")).unwrap_err();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|error| error.span.as_ref().unwrap().line)
+            .collect();
+
+        assert_eq!(lines, [2, 3]);
+        assert!(matches!(
+            errors[0].kind,
+            ParseErrorKind::IllegalInstruction(..)
+        ));
+    }
+}
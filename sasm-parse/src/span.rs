@@ -0,0 +1,132 @@
+use std::{
+    fmt::{self, Display},
+    iter,
+    ops::Range,
+    rc::Rc,
+};
+
+/// A range of columns on a single line of source code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    /// Path of the file containing the code, if it came from one.
+    pub file: Option<Rc<str>>,
+    /// Physical line number _(1-based)_.
+    pub line: usize,
+    /// Character columns covered by this span _(0-based, end-exclusive)_.
+    pub columns: Range<usize>,
+}
+
+/// Location of every part of a parsed instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct InstrSpan {
+    /// Location of the mnemonic (eg. `MOV`).
+    pub mnemonic: Span,
+    /// Location of each argument, in order.
+    pub args: Vec<Span>,
+}
+
+impl Span {
+    #[must_use]
+    pub const fn new(file: Option<Rc<str>>, line: usize, columns: Range<usize>) -> Self {
+        Self {
+            file,
+            line,
+            columns,
+        }
+    }
+
+    /// Returns a span on the same line covering the given columns.
+    #[must_use]
+    pub fn with_columns(&self, columns: Range<usize>) -> Self {
+        Self::new(self.file.clone(), self.line, columns)
+    }
+
+    /// Returns a span starting at `self` and ending where `other` ends.
+    #[must_use]
+    pub fn to(&self, other: &Self) -> Self {
+        self.with_columns(self.columns.start..other.columns.end)
+    }
+
+    /// Returns the 1-based column at which this span starts.
+    #[must_use]
+    pub const fn column(&self) -> usize {
+        self.columns.start + 1
+    }
+
+    /// Returns the part of `source_line` covered by this span.
+    ///
+    /// ```rust
+    /// # use sasm_parse::span::Span;
+    /// let span = Span::new(None, 1, 4..6);
+    ///
+    /// assert_eq!(span.text("MOV x$,1"), "x$");
+    /// ```
+    #[must_use]
+    pub fn text<'a>(&self, source_line: &'a str) -> &'a str {
+        let byte_pos = |column| {
+            source_line
+                .char_indices()
+                .nth(column)
+                .map_or(source_line.len(), |(pos, _)| pos)
+        };
+
+        &source_line[byte_pos(self.columns.start)..byte_pos(self.columns.end)]
+    }
+
+    /// Creates a line of carets (`^`) underlining this span in `source_line`.
+    ///
+    /// Tabs preceding the span are kept, so the carets stay aligned with the source.
+    ///
+    /// ```rust
+    /// # use sasm_parse::span::Span;
+    /// let span = Span::new(None, 1, 4..6);
+    ///
+    /// assert_eq!(span.underline("MOV x$,1"), "    ^^");
+    /// ```
+    #[must_use]
+    pub fn underline(&self, source_line: &str) -> String {
+        let mut output: String = source_line
+            .chars()
+            .chain(iter::repeat(' '))
+            .take(self.columns.start)
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let width = self.columns.len().max(1);
+        output.extend(iter::repeat_n('^', width));
+
+        output
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}, ")?;
+        }
+
+        write!(f, "line {}, column {}", self.line, self.column())
+    }
+}
+
+impl InstrSpan {
+    /// Returns the span covering the whole instruction, from the mnemonic to the last argument.
+    #[must_use]
+    pub fn full(&self) -> Span {
+        self.args
+            .last()
+            .map_or_else(|| self.mnemonic.clone(), |last| self.mnemonic.to(last))
+    }
+
+    /// Returns the span of the `n`-th argument, or the whole instruction if there's no such argument.
+    #[must_use]
+    pub fn arg_or_full(&self, n: usize) -> Span {
+        self.args.get(n).cloned().unwrap_or_else(|| self.full())
+    }
+
+    /// Returns the physical line number of the instruction.
+    #[must_use]
+    pub const fn line(&self) -> usize {
+        self.mnemonic.line
+    }
+}
@@ -0,0 +1,364 @@
+use crate::{error::ParseErrorKind, ident::Identifier};
+use std::{
+    fmt::{self, Display, Write},
+    iter::Peekable,
+    str::Chars,
+};
+
+/// A parsed `FMT` format string.
+///
+/// Placeholders are written as `{name}` or `{name:spec}`, where `spec` follows a subset of Rust's
+/// [format spec](https://doc.rust-lang.org/std/fmt/index.html#formatting-parameters):
+/// `[[fill]align][0][width][.precision][type]`. Literal braces are written as `{{` and `}}`.
+///
+/// ```rust
+/// # use sasm_parse::template::{Segment, Template};
+/// let template = Template::parse("{{x}} = {x:>5.2}").unwrap();
+///
+/// assert_eq!(template.segments().len(), 2);
+/// assert_eq!(template.segments()[0], Segment::Literal("{x} = ".into()));
+/// assert_eq!(template.to_string(), "{{x}} = {x:>5.2}");
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Template(Vec<Segment>);
+
+/// A part of a [`Template`].
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Segment {
+    /// Text that is copied as-is _(with `{{` and `}}` already unescaped)_.
+    Literal(String),
+    /// A variable whose value is inserted.
+    Placeholder(Identifier, FormatSpec),
+}
+
+/// Describes how a value inserted by a placeholder is formatted.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FormatSpec {
+    /// The character used for padding.
+    pub fill: char,
+    /// The alignment inside the padding. If not set, numbers are aligned right and everything else left.
+    pub align: Option<Align>,
+    /// Pads numbers with zeros after the sign.
+    pub zero_pad: bool,
+    /// The minimum width _(in characters, at most [`FormatSpec::MAX`])_.
+    pub width: Option<usize>,
+    /// The number of digits after the decimal point for floats, or the maximum length of strings
+    /// _(at most [`FormatSpec::MAX`])_.
+    pub precision: Option<usize>,
+    pub kind: FormatKind,
+}
+
+/// Alignment of a formatted value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How a value is converted into text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FormatKind {
+    /// The same text `DMP` would print.
+    #[default]
+    Display,
+    /// Lowercase hexadecimal _(numbers only)_.
+    LowerHex,
+    /// Uppercase hexadecimal _(numbers only)_.
+    UpperHex,
+    /// Binary _(numbers only)_.
+    Binary,
+}
+
+type Cursor<'a> = Peekable<Chars<'a>>;
+
+impl Template {
+    /// Parses a format string.
+    ///
+    /// # Errors
+    /// Returns [`ParseErrorKind::IllegalFormat`] if a brace is not matched, a placeholder contains an
+    /// invalid variable name or an invalid format spec.
+    pub fn parse(text: &str) -> Result<Self, ParseErrorKind> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '{' => {
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+
+                    segments.push(Self::parse_placeholder(&mut chars)?);
+                }
+                '}' => return Err(illegal("unmatched `}`")),
+                other => literal.push(other),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self(segments))
+    }
+
+    /// Returns the literals and placeholders of this template, in order.
+    #[must_use]
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    /// Returns the names of all variables used by this template.
+    pub fn variables(&self) -> impl Iterator<Item = &Identifier> {
+        self.0.iter().filter_map(|segment| match segment {
+            Segment::Placeholder(name, _) => Some(name),
+            Segment::Literal(..) => None,
+        })
+    }
+
+    fn parse_placeholder(chars: &mut Cursor) -> Result<Segment, ParseErrorKind> {
+        let mut inner = String::new();
+
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some('{') | None => return Err(illegal("unclosed placeholder")),
+                Some(ch) => inner.push(ch),
+            }
+        }
+
+        let (name, spec) = inner.split_once(':').unwrap_or((&inner, ""));
+        let name = Identifier::try_from(name)
+            .ok()
+            .filter(|_| !name.is_empty())
+            .ok_or_else(|| illegal(&format!("invalid variable name `{name}`")))?;
+        let spec = FormatSpec::parse(spec)
+            .ok_or_else(|| illegal(&format!("invalid format spec `{spec}`")))?;
+
+        Ok(Segment::Placeholder(name, spec))
+    }
+}
+
+impl FormatSpec {
+    /// The largest allowed width and precision, the same limit Rust uses.
+    pub const MAX: usize = u16::MAX as usize;
+
+    fn parse(spec: &str) -> Option<Self> {
+        let mut result = Self::default();
+        let mut chars = spec.chars().peekable();
+
+        let mut lookahead = spec.chars();
+        let first = lookahead.next();
+        let second = lookahead.next();
+
+        if let Some(align) = second.and_then(Align::from_char) {
+            result.fill = first?;
+            result.align = Some(align);
+            chars.nth(1);
+        } else if let Some(align) = first.and_then(Align::from_char) {
+            result.align = Some(align);
+            chars.next();
+        }
+
+        result.zero_pad = chars.next_if_eq(&'0').is_some();
+        result.width = parse_number(&mut chars)?;
+
+        if chars.next_if_eq(&'.').is_some() {
+            result.precision = Some(parse_number(&mut chars)??);
+        }
+
+        result.kind = match chars.next() {
+            None => return Some(result),
+            Some('x') => FormatKind::LowerHex,
+            Some('X') => FormatKind::UpperHex,
+            Some('b') => FormatKind::Binary,
+            Some(_) => return None,
+        };
+
+        chars.next().is_none().then_some(result)
+    }
+}
+
+impl Default for FormatSpec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            zero_pad: false,
+            width: None,
+            precision: None,
+            kind: FormatKind::Display,
+        }
+    }
+}
+
+impl Align {
+    const fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            '<' => Some(Self::Left),
+            '^' => Some(Self::Center),
+            '>' => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    const fn as_char(self) -> char {
+        match self {
+            Self::Left => '<',
+            Self::Center => '^',
+            Self::Right => '>',
+        }
+    }
+}
+
+/// Parses a width or precision. Returns `Some(None)` if there are no digits, and `None` if the
+/// number is larger than [`FormatSpec::MAX`].
+fn parse_number(chars: &mut Cursor) -> Option<Option<usize>> {
+    let mut digits = String::new();
+
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+
+    if digits.is_empty() {
+        return Some(None);
+    }
+
+    digits
+        .parse()
+        .ok()
+        .filter(|number| *number <= FormatSpec::MAX)
+        .map(Some)
+}
+
+fn illegal(reason: &str) -> ParseErrorKind {
+    ParseErrorKind::IllegalFormat(reason.into())
+}
+
+impl Display for Template {
+    /// Writes the template back in source form _(without quotes)_.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.0 {
+            match segment {
+                Segment::Literal(text) => {
+                    f.write_str(&text.replace('{', "{{").replace('}', "}}"))?;
+                }
+                Segment::Placeholder(name, spec) if *spec == FormatSpec::default() => {
+                    write!(f, "{{{name}}}")?;
+                }
+                Segment::Placeholder(name, spec) => write!(f, "{{{name}:{spec}}}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for FormatSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(align) = self.align {
+            if self.fill != ' ' {
+                f.write_char(self.fill)?;
+            }
+            f.write_char(align.as_char())?;
+        }
+        if self.zero_pad {
+            f.write_char('0')?;
+        }
+        if let Some(width) = self.width {
+            write!(f, "{width}")?;
+        }
+        if let Some(precision) = self.precision {
+            write!(f, ".{precision}")?;
+        }
+
+        match self.kind {
+            FormatKind::Display => Ok(()),
+            FormatKind::LowerHex => f.write_char('x'),
+            FormatKind::UpperHex => f.write_char('X'),
+            FormatKind::Binary => f.write_char('b'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholder(name: &str, spec: FormatSpec) -> Segment {
+        Segment::Placeholder(Identifier::new(name), spec)
+    }
+
+    #[test]
+    fn parse_templates() {
+        let template = Template::parse("a {{b}} {c}{d:*^08.3x}").unwrap();
+
+        assert_eq!(
+            template.segments(),
+            [
+                Segment::Literal("a {b} ".into()),
+                placeholder("c", FormatSpec::default()),
+                placeholder(
+                    "d",
+                    FormatSpec {
+                        fill: '*',
+                        align: Some(Align::Center),
+                        zero_pad: true,
+                        width: Some(8),
+                        precision: Some(3),
+                        kind: FormatKind::LowerHex,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            Template::parse("{x:>}").unwrap().segments(),
+            [placeholder(
+                "x",
+                FormatSpec {
+                    align: Some(Align::Right),
+                    ..FormatSpec::default()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in ["", "{{}}", "x = {x}!", "{x:<5}{y:0>3}{z:^10.2}{w:08b}{v:X}"] {
+            assert_eq!(Template::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for text in [
+            "{",
+            "}",
+            "{x",
+            "a}b",
+            "{}",
+            "{1}",
+            "{x:y}",
+            "{x:5.}",
+            "{x:x5}",
+            "{x{y}}",
+            "{x:65536}",
+            "{x:.65536}",
+            "{x:9999999999999}",
+            "{x:99999999999999999999}",
+        ] {
+            assert!(
+                matches!(
+                    Template::parse(text),
+                    Err(ParseErrorKind::IllegalFormat(..))
+                ),
+                "{text}"
+            );
+        }
+    }
+}
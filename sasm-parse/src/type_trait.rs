@@ -1,5 +1,5 @@
 use crate::{
-    expression::{Expression, Float, Number, Text},
+    expression::{Expression, Float, List, Map, Number, Text},
     ident::Identifier,
 };
 
@@ -31,3 +31,15 @@ impl SasmType for Float {
         Expression::FLOAT_TYPE_NAME
     }
 }
+
+impl SasmType for List {
+    fn type_name() -> &'static str {
+        Expression::LIST_TYPE_NAME
+    }
+}
+
+impl SasmType for Map {
+    fn type_name() -> &'static str {
+        Expression::MAP_TYPE_NAME
+    }
+}
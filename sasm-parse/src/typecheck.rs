@@ -0,0 +1,456 @@
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    expression::Expression,
+    ident::Identifier,
+    program::Program,
+    span::Span,
+    template::{FormatKind, Segment},
+    Instruction, SpannedInstruction,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+};
+
+/// The type of a value, as far as it can be known without running a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Number,
+    Float,
+    String,
+    List,
+    Map,
+}
+
+const NUMERIC: &[Type] = &[Type::Number, Type::Float];
+const TEXT: &[Type] = &[Type::String];
+const INTEGER: &[Type] = &[Type::Number];
+const SEQUENCE: &[Type] = &[Type::String, Type::List];
+const CONTAINER: &[Type] = &[Type::List, Type::Map];
+
+impl Type {
+    /// Returns the type of a literal, or `None` for identifiers.
+    #[must_use]
+    pub const fn of(expr: &Expression) -> Option<Self> {
+        match expr {
+            Expression::Number(..) => Some(Self::Number),
+            Expression::Float(..) => Some(Self::Float),
+            Expression::String(..) => Some(Self::String),
+            Expression::List(..) => Some(Self::List),
+            Expression::Map(..) => Some(Self::Map),
+            Expression::Identifier(..) => None,
+        }
+    }
+
+    /// Returns the same name [`Expression::type_name()`] uses.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Number => Expression::NUMBER_TYPE_NAME,
+            Self::Float => Expression::FLOAT_TYPE_NAME,
+            Self::String => Expression::STRING_TYPE_NAME,
+            Self::List => Expression::LIST_TYPE_NAME,
+            Self::Map => Expression::MAP_TYPE_NAME,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Checks the types used by a program before it's executed.
+///
+/// The type of each variable is inferred from its first assignment, in the order of the source code
+/// _(starting over after `VAR` and `DEL`)_. Later assignments of another type, math on non-numeric
+/// variables and mixing up numbers and floats are reported. Values whose type can't be known in
+/// advance, like elements of lists, are not checked.
+///
+/// An instruction which can be reached by a jump, or by returning from a `CALL`, may run after
+/// any other instruction, so all types are forgotten there and inferred again.
+///
+/// ```rust
+/// # use sasm_parse::{program::Program, typecheck};
+/// let program = Program::parse("VAR x\nMOV x,1\nRSV x").unwrap();
+/// let errors = typecheck::check(&program).unwrap_err();
+///
+/// assert_eq!(errors[0].to_string(), "Variable 'x' has type Number, but is assigned a String");
+/// ```
+///
+/// # Errors
+/// Returns every type error found in the program, ordered by line.
+pub fn check(program: &Program) -> Result<(), Vec<ParseError>> {
+    let mut checker = TypeChecker::default();
+    let entry_points = entry_points(program);
+
+    for (pos, instr) in program.instructions().iter().enumerate() {
+        if entry_points.contains(&pos) {
+            checker.types.clear();
+        }
+
+        checker.check(instr);
+    }
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
+
+/// Returns the indices of instructions which can be reached other than from the instruction before them.
+fn entry_points(program: &Program) -> HashSet<usize> {
+    let mut entry_points = HashSet::new();
+
+    for (pos, instr) in program.instructions().iter().enumerate() {
+        // Jumps outside of the program are already rejected by the parser
+        if let Some(dest) = instr
+            .instr
+            .jump_target()
+            .and_then(|target| program.jump_destination(pos, target))
+        {
+            entry_points.insert(dest);
+        }
+
+        if matches!(instr.instr, Instruction::Call(..)) {
+            entry_points.insert(pos + 1);
+        }
+    }
+
+    entry_points
+}
+
+#[derive(Default)]
+struct TypeChecker {
+    types: HashMap<Identifier, Type>,
+    errors: Vec<ParseError>,
+}
+
+impl TypeChecker {
+    fn check(&mut self, instr: &SpannedInstruction) {
+        let span = |n: usize| instr.span.arg_or_full(n);
+
+        // The assigned variable, the type of the assigned value and the argument pointing at the variable
+        let (dst, ty, arg) = match &instr.instr {
+            Instruction::CreateVariable(ident) | Instruction::Delete(ident) => {
+                self.types.remove(ident);
+                return;
+            }
+            Instruction::Move(dst, src) => (dst, self.type_of(src), 0),
+            Instruction::Increment(ident) | Instruction::Decrement(ident) => {
+                self.require_var(ident, NUMERIC, &span(0));
+                return;
+            }
+            Instruction::Add(ident, expr)
+            | Instruction::Subtract(ident, expr)
+            | Instruction::Multiply(ident, expr)
+            | Instruction::Divide(ident, expr)
+            | Instruction::Power(ident, expr) => {
+                let operands = match self.types.get(ident) {
+                    Some(Type::Number) => INTEGER,
+                    _ => NUMERIC,
+                };
+
+                self.require_var(ident, NUMERIC, &span(0));
+                self.require(expr, operands, &span(1));
+                return;
+            }
+            Instruction::SquareRoot(ident)
+            | Instruction::Sine(ident)
+            | Instruction::Cosine(ident)
+            | Instruction::Logarithm(ident)
+            | Instruction::Floor(ident)
+            | Instruction::Ceil(ident)
+            | Instruction::Round(ident) => {
+                self.require_var(ident, &[Type::Float], &span(0));
+                return;
+            }
+            Instruction::ReadNumericValue(dst)
+            | Instruction::IntoNumber(dst, _)
+            | Instruction::Length(dst, _)
+            | Instruction::Find(dst, ..)
+            | Instruction::CharCode(dst, _) => (dst, Some(Type::Number), 0),
+            Instruction::GenerateRandomNumber(dst, min, max) => {
+                for (n, bound) in [min, max].into_iter().enumerate() {
+                    if let Some(bound) = bound {
+                        self.require(bound, INTEGER, &span(n + 1));
+                    }
+                }
+
+                (dst, Some(Type::Number), 0)
+            }
+            Instruction::ReadStringValue(dst)
+            | Instruction::IntoString(dst, _)
+            | Instruction::TypeOf(dst, _)
+            | Instruction::Substring(dst, ..)
+            | Instruction::CharFromCode(dst, _)
+            | Instruction::Format(dst, _) => (dst, Some(Type::String), 0),
+            Instruction::IntoFloat(dst, _) => (dst, Some(Type::Float), 0),
+            Instruction::CreateList(dst)
+            | Instruction::Keys(dst, _)
+            | Instruction::Split(dst, ..) => (dst, Some(Type::List), 0),
+            Instruction::CreateMap(dst) => (dst, Some(Type::Map), 0),
+            Instruction::Push(ident, _) | Instruction::Reverse(ident) => {
+                self.require_var(ident, SEQUENCE, &span(0));
+                return;
+            }
+            Instruction::Pop(ident, dst) => {
+                self.require_var(ident, SEQUENCE, &span(0));
+
+                match (dst, self.types.get(ident)) {
+                    (Some(dst), Some(Type::String)) => (dst, Some(Type::String), 1),
+                    _ => return,
+                }
+            }
+            Instruction::GetItem(dst, container, key) => {
+                self.require_var(container, &[Type::List, Type::Map, Type::String], &span(1));
+                self.require_key(container, key, &span(2));
+
+                match self.types.get(container) {
+                    Some(Type::String) => (dst, Some(Type::String), 0),
+                    _ => return,
+                }
+            }
+            Instruction::SetItem(container, key, _)
+            | Instruction::RemoveItem(container, key, _) => {
+                self.require_var(container, CONTAINER, &span(0));
+                self.require_key(container, key, &span(1));
+                return;
+            }
+            Instruction::InsertItem(list, index, _) => {
+                self.require_var(list, &[Type::List], &span(0));
+                self.require(index, INTEGER, &span(1));
+                return;
+            }
+            Instruction::HasKey(map, key) => {
+                self.require_var(map, &[Type::Map], &span(0));
+                self.require(key, TEXT, &span(1));
+                return;
+            }
+            Instruction::Uppercase(ident)
+            | Instruction::Lowercase(ident)
+            | Instruction::Trim(ident) => {
+                self.require_var(ident, TEXT, &span(0));
+                return;
+            }
+            Instruction::Replace(ident, from, to) => {
+                self.require_var(ident, TEXT, &span(0));
+                self.require(from, TEXT, &span(1));
+                self.require(to, TEXT, &span(2));
+                return;
+            }
+            Instruction::Repeat(ident, count) => {
+                self.require_var(ident, TEXT, &span(0));
+                self.require(count, INTEGER, &span(1));
+                return;
+            }
+            Instruction::Sleep(time) => {
+                self.require(time, INTEGER, &span(0));
+                return;
+            }
+            Instruction::Dump(..)
+            | Instruction::Compare(..)
+            | Instruction::JumpEqual(..)
+            | Instruction::JumpNotEqual(..)
+            | Instruction::JumpLess(..)
+            | Instruction::JumpGreater(..)
+            | Instruction::JumpLessEqual(..)
+            | Instruction::JumpGreaterEqual(..)
+            | Instruction::Jump(..)
+            | Instruction::Call(..)
+            | Instruction::Return
+            | Instruction::Print(..)
+            | Instruction::Clear(..)
+            | Instruction::Die(..) => return,
+        };
+
+        self.check_operands(instr);
+        self.assign(dst, ty, &span(arg));
+    }
+
+    /// Checks the operands of instructions which also assign a value.
+    fn check_operands(&mut self, instr: &SpannedInstruction) {
+        let span = |n: usize| instr.span.arg_or_full(n);
+
+        match &instr.instr {
+            Instruction::Keys(_, map) => self.require_var(map, &[Type::Map], &span(1)),
+            Instruction::Length(_, obj) => {
+                self.require(obj, &[Type::String, Type::List, Type::Map], &span(1));
+            }
+            Instruction::Substring(_, src, start, end) => {
+                self.require(src, TEXT, &span(1));
+                self.require(start, INTEGER, &span(2));
+
+                if let Some(end) = end {
+                    self.require(end, INTEGER, &span(3));
+                }
+            }
+            Instruction::Find(_, src, other) | Instruction::Split(_, src, other) => {
+                self.require(src, TEXT, &span(1));
+                self.require(other, TEXT, &span(2));
+            }
+            Instruction::CharCode(_, src) => self.require(src, TEXT, &span(1)),
+            Instruction::CharFromCode(_, code) => self.require(code, INTEGER, &span(1)),
+            Instruction::Format(_, template) => {
+                for segment in template.segments() {
+                    if let Segment::Placeholder(name, spec) = segment {
+                        if spec.kind != FormatKind::Display {
+                            self.require_var(name, INTEGER, &span(1));
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn type_of(&self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Identifier(ident) => self.types.get(ident).copied(),
+            other => Type::of(other),
+        }
+    }
+
+    /// Records the type of a variable, or reports an error if it already has a different type.
+    /// Values of unknown type are ignored.
+    fn assign(&mut self, ident: &Identifier, ty: Option<Type>, span: &Span) {
+        let Some(ty) = ty else {
+            return;
+        };
+
+        match self.types.get(ident) {
+            Some(current) if *current != ty => self.errors.push(
+                ParseErrorKind::ConflictingAssignment {
+                    name: ident.to_string(),
+                    got: ty.name().into(),
+                    expected: current.name().into(),
+                }
+                .at(span),
+            ),
+            _ => {
+                self.types.insert(ident.clone(), ty);
+            }
+        }
+    }
+
+    fn require(&mut self, expr: &Expression, allowed: &[Type], span: &Span) {
+        let Some(ty) = self.type_of(expr) else {
+            return;
+        };
+
+        if !allowed.contains(&ty) {
+            let expected: Vec<_> = allowed.iter().map(|ty| ty.name()).collect();
+
+            self.errors.push(
+                ParseErrorKind::MismatchedTypes {
+                    got: ty.name().into(),
+                    expected: expected.join(" or "),
+                }
+                .at(span),
+            );
+        }
+    }
+
+    fn require_var(&mut self, ident: &Identifier, allowed: &[Type], span: &Span) {
+        self.require(&Expression::Identifier(ident.clone()), allowed, span);
+    }
+
+    /// Lists and strings are indexed by numbers, maps by strings.
+    fn require_key(&mut self, container: &Identifier, key: &Expression, span: &Span) {
+        match self.types.get(container) {
+            Some(Type::List | Type::String) => self.require(key, INTEGER, span),
+            Some(Type::Map) => self.require(key, TEXT, span),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{error::ParseErrorKind, program::Program};
+
+    fn errors(source: &str) -> Vec<(usize, String)> {
+        check(&Program::parse(source).unwrap()).map_or_else(
+            |errors| {
+                errors
+                    .into_iter()
+                    .map(|error| (error.span.unwrap().line, error.kind.to_string()))
+                    .collect()
+            },
+            |()| Vec::new(),
+        )
+    }
+
+    #[test]
+    fn valid_programs() {
+        let sources = [
+            "VAR x\nMOV x,1\nADD x,2\nVAR f\nFLT f,x\nADD f,x\nSQRT f\nVAR s\nFMT s,\"{x:x}\"\nPSH s,x",
+            "VAR x\nMOV x,\"a\"\nDEL x\nVAR x\nMOV x,1",
+            "VAR xs\nLST xs\nPSH xs,1\nVAR x\nGET x,xs,0\nMOV x,\"?\"",
+            "VAR m\nMAP m\nSET m,\"a\",1\nVAR k\nKEY k,m\nVAR n\nLEN n,k",
+        ];
+
+        for source in sources {
+            assert_eq!(errors(source), Vec::new(), "{source}");
+        }
+    }
+
+    #[test]
+    fn jumps_forget_types() {
+        let sources = [
+            "VAR x\nMOV x,1\nJMP skip\nDEL x\nVAR x\nMOV x,\"a\"\nskip:\nADD x,1",
+            "VAR x\nMOV x,1\nCALL text\nUPPER x\nDIE\ntext:\nDEL x\nVAR x\nRSV x\nRET",
+        ];
+
+        for source in sources {
+            assert_eq!(errors(source), Vec::new(), "{source}");
+        }
+
+        assert_eq!(
+            errors("VAR x\nloop:\nMOV x,1\nMOV x,\"a\"\nJMP loop"),
+            vec![(
+                4,
+                "Variable 'x' has type Number, but is assigned a String".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn conflicting_assignments() {
+        assert_eq!(
+            errors("VAR x\nRNV x\nMOV x,\"a\"\nVAR s\nMOV s,x\nRSV s"),
+            vec![
+                (
+                    3,
+                    "Variable 'x' has type Number, but is assigned a String".into()
+                ),
+                (
+                    6,
+                    "Variable 's' has type Number, but is assigned a String".into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_operations() {
+        let found = errors(
+            "VAR s\nRSV s\nINC s\nVAR n\nMOV n,1\nADD n,1.5\nSQRT n\nUPPER n\nGET s,s,\"a\"",
+        );
+        let lines: Vec<_> = found.iter().map(|(line, _)| *line).collect();
+
+        assert_eq!(lines, vec![3, 6, 7, 8, 9]);
+        assert_eq!(
+            found[0].1,
+            ParseErrorKind::MismatchedTypes {
+                got: "String".into(),
+                expected: "Number or Float".into()
+            }
+            .to_string()
+        );
+        assert_eq!(found[1].1, "Expected value of type Number, got Float");
+    }
+}